@@ -1,23 +1,51 @@
 use eframe::{egui, epi};
 use std::sync::{Arc, Mutex};
-use autolink_lib::{Plan, TimeDay};
-use crossbeam_channel;
+use autolink_lib::Plan;
 use autolink_lib::chrono;
-use chrono::{Datelike, Timelike};
-use std::iter::FromIterator;
+use chrono::Timelike;
+use crossbeam_channel;
 
 mod utils;
 mod state;
+mod week;
+mod holidays;
+mod log;
+mod corrupt;
+mod files;
+mod entries;
+mod disk;
+mod profiles;
 
 use state::{UIState, AddUIState, EditUIState, RemoveUIState};
+use self::profiles::Loaded;
+
+/// How many launches the "next up" list shows.
+const NEXT_UP: usize = 5;
+use crate::scheduler::{Scheduler, SystemClock, SchedulerCommand, SchedulerEvent};
+use crate::scheduler::occurrence;
+use crate::settings::{Settings, PlanSettings};
+use crate::{ipc, store};
+use crate::store::FileEvent;
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
 pub struct App {
+    /// The profile being edited, the plans and settings here are its.
+    profile: String,
     plans: Arc<Mutex<Vec<Plan>>>,
-    is_loop_running: bool,
-    sender: crossbeam_channel::Sender<bool>,
-    receiver: crossbeam_channel::Receiver<bool>,
+    settings: Settings,
+    /// The profile the loop runs, while another one is being edited.
+    running: Option<Loaded>,
+    /// What the socket works on, the plans of the profile the loop runs.
+    ipc_target: ipc::Target,
+    sender: crossbeam_channel::Sender<SchedulerCommand>,
+    receiver: crossbeam_channel::Receiver<SchedulerCommand>,
+    event_sender: crossbeam_channel::Sender<SchedulerEvent>,
+    events: crossbeam_channel::Receiver<SchedulerEvent>,
+    file_events: crossbeam_channel::Receiver<FileEvent>,
+    is_repainting: bool,
+    /// Started in setup, saves the plans in the background.
+    writer: Option<store::Writer>,
     state: UIState,
 }
 
@@ -26,16 +54,265 @@ pub struct App {
 impl Default for App {
     fn default() -> Self {
         let (s, r) = crossbeam_channel::unbounded();
+        let (es, er) = crossbeam_channel::unbounded();
         Self {
+            profile: String::new(),
             plans: Arc::new(Mutex::new(Vec::new())),
-            is_loop_running: false,
+            settings: Settings::default(),
+            running: None,
+            ipc_target: Arc::new(Mutex::new(Arc::new(Mutex::new(Vec::new())))),
             sender: s,
             receiver: r,
+            event_sender: es,
+            events: er,
+            file_events: crossbeam_channel::never(),
+            is_repainting: false,
+            writer: None,
             state: UIState::default(),
         }
     }
 }
 
+impl App {
+    /// Hands the current plans to the loop thread and saves them, called after every change to them.
+    fn reload_scheduler(&mut self) {
+        self.state.start.upcoming_at = None;
+        let plans = self.plans.lock().unwrap().clone();
+        self.save_plans(plans.clone());
+        if let Some(writer) = &self.writer {
+            writer.write_settings(self.settings.clone());
+        }
+        // the loop keeps running its own profile while another one is edited
+        if self.running.is_none() {
+            let _ = self.sender.send(SchedulerCommand::Reload(plans, self.settings.clone()));
+        }
+    }
+
+    /// Saves the pause of the loop with the profile it runs, so that it outlasts the app.
+    fn keep_pause(&mut self, paused_until: Option<chrono::NaiveDateTime>) {
+        let (writer, settings) = match &mut self.running {
+            Some(running) => (&running.writer, &mut running.settings),
+            None => (&self.writer, &mut self.settings),
+        };
+        settings.paused_until = paused_until;
+        if let Some(writer) = writer {
+            writer.write_settings(settings.clone());
+        }
+    }
+
+    fn save_plans(&self, plans: Vec<Plan>) {
+        if let Some(writer) = &self.writer {
+            writer.write(plans);
+        }
+    }
+
+    /// Starts the loop, the writer and the socket with the plans read from disk.
+    fn start(&mut self, plans: Vec<Plan>) {
+        let profile = self.profile.clone();
+        self.put_loaded(Loaded::open(&profile, plans, Some(self.sender.clone())));
+        self.state.profiles.names = crate::profiles::list();
+        std::thread::spawn({
+            let mut scheduler = Scheduler::new(SystemClock);
+            scheduler.record_to(crate::history::path());
+            if let Some(checked_until) = self.settings.checked_until {
+                scheduler.resume_from(checked_until);
+            }
+            let receiver = self.receiver.clone();
+            let events = self.event_sender.clone();
+            move || scheduler.run(receiver, events)
+        });
+        self.reload_scheduler();
+        *self.ipc_target.lock().unwrap() = self.plans.clone();
+        if let Err(e) = ipc::serve(self.ipc_target.clone(), self.sender.clone()) {
+            self.state.status.notice(format!("couldn't listen on {}: {}", ipc::path().display(), e));
+        }
+        match self.settings.paused_until {
+            Some(until) if until > chrono::Local::now().naive_local() => {
+                let _ = self.sender.send(SchedulerCommand::PauseUntil(until));
+            },
+            Some(_) => self.keep_pause(None),
+            None => (),
+        }
+        // the loop was running when the app was closed, so it picks up where it left off
+        if self.settings.checked_until.is_some() {
+            let _ = self.sender.send(SchedulerCommand::Start);
+        }
+    }
+
+    /*
+     * egui only repaints when there is input, the countdowns and the
+     * loop status would go stale while nobody touches the window
+     * 
+     * a repaint signal from the frame can be sent from another thread,
+     * unlike ctx.request_repaint() which repaints on every frame, so a
+     * thread passes the events of the loop on to the app and repaints
+     * for each of them, and otherwise when the minute changes
+    */
+    fn start_repainting(&mut self, frame: &mut epi::Frame<'_>) {
+        if self.is_repainting {
+            return
+        }
+        self.is_repainting = true;
+        let signal = frame.repaint_signal();
+        let (relay, events) = crossbeam_channel::unbounded();
+        let from = std::mem::replace(&mut self.events, events);
+        std::thread::spawn(move || loop {
+            let to_next_minute = 60 - chrono::Local::now().second() as u64;
+            match from.recv_timeout(std::time::Duration::from_secs(to_next_minute)) {
+                Ok(event) => if relay.send(event).is_err() {
+                    return
+                },
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => (),
+                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => return,
+            }
+            signal.request_repaint();
+        });
+    }
+
+    /// Works the "next up" list out again once a minute, or when something changed it.
+    fn upcoming(&mut self, plans: &[Plan], now: chrono::NaiveDateTime) -> &[(usize, chrono::NaiveDateTime)] {
+        let minute = now.date().and_hms(now.hour(), now.minute(), 0);
+        if self.state.start.upcoming_at != Some(minute) {
+            self.state.start.upcoming = occurrence::upcoming(plans, &self.settings, now, NEXT_UP);
+            self.state.start.upcoming_at = Some(minute);
+        }
+        &self.state.start.upcoming
+    }
+
+    fn handle_events(&mut self) {
+        let events = self.events.clone();
+        for event in events.try_iter() {
+            // launches, reloads and pauses all move what is next up
+            self.state.start.upcoming_at = None;
+            let status = &mut self.state.status;
+            match event {
+                SchedulerEvent::Started => status.is_running = true,
+                SchedulerEvent::Stopped | SchedulerEvent::ShutDown { .. } => status.is_running = false,
+                SchedulerEvent::Reloaded(entries) => {
+                    status.entries = entries;
+                    // the plans may have been changed through the socket
+                    match &self.running {
+                        Some(running) => if let Some(writer) = &running.writer {
+                            writer.write(running.plans.lock().unwrap().clone());
+                        },
+                        None => {
+                            self.save_plans(self.plans.lock().unwrap().clone());
+                            self.state.edit.follow(&self.plans.lock().unwrap());
+                        },
+                    }
+                },
+                SchedulerEvent::Paused(until) => {
+                    status.paused_until = Some(until);
+                    self.keep_pause(Some(until));
+                },
+                SchedulerEvent::Resumed => {
+                    status.paused_until = None;
+                    self.keep_pause(None);
+                },
+                SchedulerEvent::Launched { plan, link, at, error } => {
+                    if let Some(e) = error {
+                        status.notice(format!("couldn't launch {} ({}): {}", plan.name, link, e));
+                    }
+                    status.last_launch = Some((plan.name, at));
+                },
+                SchedulerEvent::Missed { plan, at } => status.missed.push((plan, at)),
+                SchedulerEvent::Gone { name } => {
+                    status.notice(format!("didn't launch {}, it was changed or removed since", name));
+                },
+                SchedulerEvent::Skipped { plan, at, quiet } => {
+                    let why = if quiet { ", it is quiet time" } else { "" };
+                    status.notice(format!("skipped {} which was due at {}{}", plan.name, at.format("%a %H:%M"), why));
+                },
+                SchedulerEvent::HistoryFailed(e) => status.notice(e),
+                SchedulerEvent::Renamed { from, to } => match &mut self.running {
+                    Some(running) => running.settings.rename(&from, &to),
+                    None => self.settings.rename(&from, &to),
+                },
+                SchedulerEvent::Forgotten(name) => {
+                    match &mut self.running {
+                        Some(running) => running.settings.plans.remove(&name),
+                        None => self.settings.plans.remove(&name),
+                    };
+                },
+                SchedulerEvent::ActionsSet { name, actions } => {
+                    let settings = match &mut self.running {
+                        Some(running) => &mut running.settings,
+                        None => &mut self.settings,
+                    };
+                    settings.plans.entry(name).or_default().actions = actions;
+                },
+                SchedulerEvent::OnceDone { name, at } => {
+                    let settings = match &mut self.running {
+                        Some(running) => &mut running.settings,
+                        None => &mut self.settings,
+                    };
+                    if let Some(settings) = settings.plans.get_mut(&name) {
+                        for once in settings.once.iter_mut().filter(|once| once.at == at) {
+                            once.done = true;
+                        }
+                    }
+                },
+                SchedulerEvent::ClockJumped { from, to } => {
+                    status.notice(format!("the clock jumped from {} to {}", from.format("%a %H:%M"), to.format("%a %H:%M")));
+                },
+            }
+        }
+        let file_events = self.file_events.clone();
+        for event in file_events.try_iter() {
+            self.state.start.upcoming_at = None;
+            if let Some(banner) = self.handle_file_event(false, event) {
+                self.state.banner = Some(banner);
+            }
+            // the entry being edited may have moved, changed or gone
+            self.state.edit.follow(&self.plans.lock().unwrap());
+        }
+        if let Some(running) = &self.running {
+            let file_events = running.file_events.clone();
+            for event in file_events.try_iter() {
+                if let Some(banner) = self.handle_file_event(true, event) {
+                    self.state.banner = Some(banner);
+                }
+            }
+        }
+    }
+
+    /// Gives what to tell about a change on disk to the profile being edited or, if `of_running`, to the one the loop runs.
+    fn handle_file_event(&mut self, of_running: bool, event: FileEvent) -> Option<String> {
+        let is_looped = of_running || self.running.is_none();
+        let (profile, plans, settings, writer) = match &mut self.running {
+            Some(running) if of_running => (running.name.clone(), running.plans.clone(), &mut running.settings, &running.writer),
+            _ => (self.profile.clone(), self.plans.clone(), &mut self.settings, &self.writer),
+        };
+        let (known, disk) = match event {
+            FileEvent::Reloaded(entries) => {
+                return Some(format!("{} changed on disk, reloaded {} entries", store::path(&profile).display(), entries))
+            },
+            FileEvent::Conflicted(names) => return Some(format!(
+                "{} changed on disk, kept the changes made here to {}", store::path(&profile).display(), names.join(", ")
+            )),
+            FileEvent::Failed(e) => return Some(e),
+            FileEvent::SettingsChanged { known, disk } => (known, disk),
+        };
+        let (merged, conflicts) = store::merge_settings(&known, settings, &disk);
+        if store::same_settings(&merged, settings) {
+            return None
+        }
+        *settings = merged;
+        if let Some(writer) = writer {
+            writer.write_settings(settings.clone());
+        }
+        if is_looped {
+            let _ = self.sender.send(SchedulerCommand::Reload(plans.lock().unwrap().clone(), settings.clone()));
+        }
+        Some(match conflicts.is_empty() {
+            true => format!("{} changed on disk, took the settings in", Settings::path(&profile).display()),
+            false => format!(
+                "{} changed on disk, kept the settings of {} as they were here", Settings::path(&profile).display(), conflicts.join(", ")
+            ),
+        })
+    }
+}
+
 impl epi::App for App {
     fn name(&self) -> &str {
         "autolink"
@@ -44,6 +321,14 @@ impl epi::App for App {
     /// Called each time the UI needs repainting, which may be many times per second.
     /// Put your widgets into a `SidePanel`, `TopPanel`, `CentralPanel`, `Window` or `Area`.
     fn update(&mut self, ctx: &egui::CtxRef, frame: &mut epi::Frame<'_>) {
+        self.start_repainting(frame);
+        if self.state.load_error.is_some() {
+            self.load_error_screen(ctx, frame);
+            return
+        }
+        self.handle_events();
+        // entries can be removed through the socket while they are selected
+        self.state.clamp_selections(self.plans.lock().unwrap().len());
         egui::containers::CentralPanel::default().show(&ctx, |ui| {
             egui::containers::TopPanel::top("sections").show(&ctx, |ui| {
                 ui.horizontal(|ui| {
@@ -52,12 +337,18 @@ impl epi::App for App {
                         String::from(["launch", "add", "edit", "remove"][i])
                     })
                     */
-                    let scenes = ["launch", "add", "edit", "remove"];
+                    let scenes = ["launch", "add", "edit", "remove", "week", "holidays", "history", "files", "entries"];
                     ui.selectable_value(&mut self.state.section, 0, scenes[0]);
                     ui.selectable_value(&mut self.state.section, 1, scenes[1]);
                     ui.selectable_value(&mut self.state.section, 2, scenes[2]);
                     ui.selectable_value(&mut self.state.section, 3, scenes[3]);
+                    ui.selectable_value(&mut self.state.section, 4, scenes[4]);
+                    ui.selectable_value(&mut self.state.section, 5, scenes[5]);
+                    ui.selectable_value(&mut self.state.section, 6, scenes[6]);
+                    ui.selectable_value(&mut self.state.section, 7, scenes[7]);
+                    ui.selectable_value(&mut self.state.section, 8, scenes[8]);
                 });
+                self.profile_bar(ui);
             });
 
             egui::containers::CentralPanel::default().show(&ctx, |ui| {
@@ -65,34 +356,114 @@ impl epi::App for App {
                     self.state.set_sections_to_default();
                     self.state.prev_section = self.state.section;
                 }
+                if let Some(banner) = &self.state.banner {
+                    let mut dismissed = false;
+                    ui.horizontal_wrapped(|ui| {
+                        ui.colored_label(egui::Color32::YELLOW, banner);
+                        dismissed = ui.small_button("dismiss").clicked();
+                    });
+                    ui.separator();
+                    if dismissed {
+                        self.state.banner = None;
+                    }
+                }
                 if self.state.section == 0 {
                     if self.plans.lock().unwrap().len() == 0 {
                         ui.add(egui::widgets::Label::new("first of all, add some entries").wrap(true));
                     } else {
                         ui.add(egui::widgets::Label::new("automagically launch entries at the right time").wrap(true));
-                        if ui.button(match self.is_loop_running {
+                        if ui.button(match self.state.status.is_running {
                             true => "stop loop",
                             false => "start loop"
                         }).clicked() {
-                            match self.is_loop_running {
-                                true => {
-                                    let _ = self.sender.send(false);
-                                    self.is_loop_running = false;
-                                },
-                                false => {
-                                    let _ = self.sender.send(true);
-                                    self.is_loop_running = true;
-                                },
-                            }
+                            let _ = self.sender.send(match self.state.status.is_running {
+                                true => SchedulerCommand::Stop,
+                                false => SchedulerCommand::Start,
+                            });
+                        }
+                        ui.add(egui::widgets::Label::new(match self.state.status.is_running {
+                            true => format!("the loop is running with {} entries", self.state.status.entries),
+                            false => String::from("the loop is stopped"),
+                        }).wrap(true));
+                        // a pause outlasts stopping the loop, so it can be set beforehand for a holiday
+                        match self.state.status.paused_until {
+                            Some(until) => {
+                                let left = until - chrono::Local::now().naive_local();
+                                ui.add(egui::widgets::Label::new(format!(
+                                    "paused until {}, resuming {}", until.format("%a %Y-%m-%d %H:%M"), utils::countdown(left)
+                                )).wrap(true));
+                                if ui.button("resume").clicked() {
+                                    let now = chrono::Local::now().naive_local();
+                                    let _ = self.sender.send(SchedulerCommand::PauseUntil(now));
+                                }
+                            },
+                            None => {
+                                if ui.button("pause for an hour").clicked() {
+                                    let until = chrono::Local::now().naive_local() + chrono::Duration::hours(1);
+                                    let _ = self.sender.send(SchedulerCommand::PauseUntil(until));
+                                }
+                                ui.collapsing("pause until a day", |ui| {
+                                    utils::date_selection_box(ui, &mut self.state.pause);
+                                    let until = self.state.pause.date().map(|date| date.and_hms(0, 0, 0));
+                                    if utils::is_ahead(until) && ui.button("pause").clicked() {
+                                        let _ = self.sender.send(SchedulerCommand::PauseUntil(until.unwrap()));
+                                    }
+                                });
+                            },
+                        }
+                        if let Some((name, at)) = &self.state.status.last_launch {
+                            ui.add(egui::widgets::Label::new(format!("last launched {} at {}", name, at.format("%H:%M"))).wrap(true));
+                        }
+                        let mut answered = None;
+                        for (i, (plan, at)) in self.state.status.missed.iter().enumerate() {
+                            ui.horizontal( |ui| {
+                                ui.add(egui::widgets::Label::new(format!("missed {} at {}", plan.name, at.format("%a %H:%M"))).wrap(true));
+                                if ui.button("launch").clicked() {
+                                    let _ = self.sender.send(SchedulerCommand::LaunchNow(plan.clone()));
+                                    answered = Some(i);
+                                }
+                                if ui.button("dismiss").clicked() {
+                                    answered = Some(i);
+                                }
+                            });
+                        }
+                        if let Some(i) = answered {
+                            self.state.status.missed.remove(i);
+                        }
+                        for notice in &self.state.status.notices {
+                            ui.add(egui::widgets::Label::new(notice).wrap(true));
                         }
                         ui.add(egui::widgets::Separator::default().horizontal());
-                        ui.add(egui::widgets::Label::new("manually select and launch an entry").wrap(true));
-                        utils::entry_selection_box(ui, self.plans.clone(), &mut self.state.start.selection);
-                        if ui.button("launch").clicked() {
-                            let plans = self.plans.lock().unwrap().clone();
-                            autolink_lib::open_link(
-                                &plans.get(self.state.start.selection).unwrap().link
-                            );
+                        ui.add(egui::widgets::Label::new("next up").wrap(true));
+                        let plans = self.plans.lock().unwrap().clone();
+                        let now = chrono::Local::now().naive_local();
+                        let upcoming = self.upcoming(&plans, now).to_vec();
+                        if upcoming.len() == 0 {
+                            ui.add(egui::widgets::Label::new("no times entered for any entry").wrap(true));
+                        }
+                        for (index, at) in upcoming {
+                            let plan = match plans.get(index) {
+                                Some(plan) => plan,
+                                None => continue,
+                            };
+                            ui.add(egui::widgets::Label::new(format!(
+                                "{} - {} {} - {} ({})",
+                                utils::countdown(at - now), at.format("%A"), at.format("%H:%M"), plan.name, plan.link
+                            )).wrap(true));
+                        }
+                        ui.add(egui::widgets::Separator::default().horizontal());
+                        if self.running.is_some() {
+                            ui.add(egui::widgets::Label::new(format!(
+                                "the loop runs {}, entries of this profile can be launched once it runs this one", self.running_profile()
+                            )).wrap(true));
+                        } else {
+                            ui.add(egui::widgets::Label::new("manually select and launch an entry").wrap(true));
+                            utils::entry_selection_box(ui, self.plans.clone(), &mut self.state.start.selection);
+                            if ui.button("launch").clicked() {
+                                if let Some(plan) = self.plans.lock().unwrap().get(self.state.start.selection) {
+                                    let _ = self.sender.send(SchedulerCommand::LaunchNow(plan.clone()));
+                                }
+                            }
                         }
                     }
                     
@@ -101,47 +472,58 @@ impl epi::App for App {
                     ui.add(egui::widgets::TextEdit::singleline(&mut self.state.add.name).hint_text("name"));
                     ui.add(egui::widgets::Label::new("link of entry").wrap(true));
                     ui.add(egui::widgets::TextEdit::singleline(&mut self.state.add.link).hint_text("link"));
+                    ui.add(egui::widgets::Label::new("then it opens other links or runs commands").wrap(true));
+                    if let Err(e) = utils::action_list(ui, &mut self.state.add.actions, &mut self.state.add.action) {
+                        self.state.add.output = e;
+                    }
                     ui.add(egui::widgets::Separator::default().horizontal());
                     ui.add(egui::widgets::Checkbox::new(&mut self.state.add.add_time, "add time?"));
                     if self.state.add.add_time {
-                        utils::day_selection_box(ui, &mut self.state.add.selected_day);
-                        ui.horizontal( |ui| {
-                            ui.add(egui::widgets::DragValue::new(&mut self.state.add.hour).clamp_range(0..=23));
-                            ui.add(egui::widgets::Label::new("hour").wrap(true));
-                            ui.add(egui::widgets::DragValue::new(&mut self.state.add.minute).clamp_range(0..=59));
-                            ui.add(egui::widgets::Label::new("minute").wrap(true));
-                        });
+                        utils::time_selection_box(ui, &mut self.state.add.time);
                     }
                     ui.add(egui::widgets::Separator::default().horizontal());
+                    utils::launcher_selection_box(ui, &mut self.state.add.launcher);
+                    ui.add(egui::widgets::Separator::default().horizontal());
                     if ui.button("add").clicked() {
-                        if !(self.state.add.name == "" || self.state.add.link == "") {
-                            let mut plans = self.plans.lock().unwrap();
-                            plans.push(Plan {
+                        let picked = match self.state.add.add_time {
+                            true => self.state.add.time.pick().map(Some),
+                            false => Ok(None),
+                        };
+                        let launcher = self.state.add.launcher.pick();
+                        let taken = store::check_name(&self.plans.lock().unwrap(), &self.state.add.name, None);
+                        if self.state.add.name == "" || self.state.add.link == "" {
+                            self.state.add.output = String::from("both name and link must be entered!");
+                        } else if let Err(e) = taken {
+                            self.state.add.output = e;
+                        } else if let Err(e) = picked {
+                            self.state.add.output = e;
+                        } else if let Err(e) = launcher {
+                            self.state.add.output = e;
+                        } else {
+                            let mut plan = Plan {
                                 name: self.state.add.name.clone(),
                                 link: self.state.add.link.clone(),
-                                times: match self.state.add.add_time {
-                                    true => { vec![
-                                        TimeDay {
-                                            day: utils::usize_to_day(self.state.add.selected_day),
-                                            time: autolink_lib::chrono::NaiveTime::from_num_seconds_from_midnight((self.state.add.hour * 3600 + self.state.add.minute * 60) as u32, 0),
-                                        }
-                                    ] },
-                                    false => { vec![] },
-                                }
-                            });
+                                times: vec![],
+                            };
+                            let mut settings = PlanSettings::default();
+                            settings.actions = self.state.add.actions.clone();
+                            settings.launcher = launcher.unwrap();
+                            if let Ok(Some(picked)) = picked {
+                                utils::add_time(&mut plan, &mut settings, picked);
+                            }
+                            let mut plans = self.plans.lock().unwrap();
+                            plans.push(plan);
                             drop(plans);
                             let name = self.state.add.name.clone();
+                            self.settings.plans.insert(name.clone(), settings);
                             self.state.add = AddUIState::default();
                             self.state.add.output = format!("entry {} has been added", name);
-                            if self.is_loop_running {
-                                let _ = self.sender.send(false);
-                                let _ = self.sender.send(true);
-                            }
-                        } else {
-                            self.state.add.output = String::from("both name and link must be entered!");
+                            self.reload_scheduler();
                         }
                     }
                     ui.add(egui::widgets::Label::new(self.state.add.output.clone()).wrap(true));
+                } else if self.state.section == 2 && self.state.edit.disk_change.is_some() {
+                    self.disk_change_section(ui);
                 } else if self.state.section == 2 {
                     if self.plans.lock().unwrap().len() == 0 {
                         ui.add(egui::widgets::Label::new("first of all, add some entries").wrap(true));
@@ -150,32 +532,31 @@ impl epi::App for App {
                         ui.add(egui::widgets::Separator::default().horizontal());
                         if self.state.edit.selection != self.state.edit.prev_selection {
                             self.state.edit.prev_selection = self.state.edit.selection;
-                            self.state.edit.plan = self.plans.lock().unwrap().get(self.state.edit.selection).unwrap().clone();
+                            let plan = self.plans.lock().unwrap().get(self.state.edit.selection).unwrap().clone();
+                            let settings = self.settings.plan(&plan.name);
+                            self.state.edit.load(plan, settings);
                         }
                         ui.add(egui::widgets::Label::new("name of entry").wrap(true));
                         ui.add(egui::widgets::TextEdit::singleline(&mut self.state.edit.plan.name).hint_text("name"));
                         ui.add(egui::widgets::Label::new("link of entry").wrap(true));
                         ui.add(egui::widgets::TextEdit::singleline(&mut self.state.edit.plan.link).hint_text("link"));
+                        ui.add(egui::widgets::Label::new("then it opens other links or runs commands").wrap(true));
+                        if let Err(e) = utils::action_list(ui, &mut self.state.edit.settings.actions, &mut self.state.edit.action) {
+                            self.state.edit.output = e;
+                        }
                         ui.add(egui::widgets::Separator::default().horizontal());
                         ui.add(egui::widgets::Checkbox::new(&mut self.state.edit.add_time, "add time?"));
                         if self.state.edit.add_time {
-                            utils::day_selection_box(ui, &mut self.state.edit.selected_day);
-                            ui.horizontal( |ui| {
-                                ui.add(egui::widgets::DragValue::new(&mut self.state.edit.hour).clamp_range(0..=23));
-                                ui.add(egui::widgets::Label::new("hour").wrap(true));
-                                ui.add(egui::widgets::DragValue::new(&mut self.state.edit.minute).clamp_range(0..=59));
-                                ui.add(egui::widgets::Label::new("minute").wrap(true));
-                            });
+                            utils::time_selection_box(ui, &mut self.state.edit.time);
                         }
                         ui.add(egui::widgets::Separator::default().horizontal());
                         egui::containers::Frame::default().show(ui, |ui| {
-                            if self.state.edit.plan.times.len() != 0 {
+                            let labels = utils::time_labels(&self.state.edit.plan, &self.state.edit.settings);
+                            if labels.len() != 0 {
                                 ui.add(egui::widgets::Checkbox::new(&mut self.state.edit.remove_time, "remove time?"));
                                 if self.state.edit.remove_time {
-                                    let plan = self.state.edit.plan.clone();
-                                    egui::containers::ComboBox::from_label("select time").show_index(ui, &mut self.state.edit.selected_time, self.state.edit.plan.times.len(), |i| {
-                                        let TimeDay { day, time } = plan.times.get(i).unwrap();
-                                        format!("{} - {}", day, time)
+                                    egui::containers::ComboBox::from_label("select time").show_index(ui, &mut self.state.edit.selected_time, labels.len(), |i| {
+                                        labels.get(i).unwrap().clone()
                                     });
                                 }
                             } else {
@@ -185,29 +566,62 @@ impl epi::App for App {
                         
                         });
                         ui.add(egui::widgets::Separator::default().horizontal());
+                        utils::launcher_selection_box(ui, &mut self.state.edit.launcher);
+                        ui.add(egui::widgets::Separator::default().horizontal());
+                        utils::catch_up_selection_box(ui, &mut self.state.edit.catch_up, &mut self.state.edit.grace_minutes);
+                        ui.add(egui::widgets::Separator::default().horizontal());
+                        ui.add(egui::widgets::Checkbox::new(&mut self.state.edit.has_from, "starts on a date?"));
+                        if self.state.edit.has_from {
+                            utils::date_selection_box(ui, &mut self.state.edit.from);
+                        }
+                        ui.add(egui::widgets::Checkbox::new(&mut self.state.edit.has_until, "ends on a date?"));
+                        if self.state.edit.has_until {
+                            utils::date_selection_box(ui, &mut self.state.edit.until);
+                        }
+                        ui.add(egui::widgets::Label::new("skipped dates").wrap(true));
+                        utils::date_list(ui, &mut self.state.edit.settings.skipped, &mut self.state.edit.skip);
+                        ui.add(egui::widgets::Separator::default().horizontal());
                         if ui.button("edit").clicked() {
-                            if !(self.state.edit.plan.name == "" || self.state.edit.plan.link == "") {
-                                if self.state.edit.add_time {
-                                    self.state.edit.plan.times.push(TimeDay {
-                                        day: utils::usize_to_day(self.state.edit.selected_day),
-                                        time: autolink_lib::chrono::NaiveTime::from_num_seconds_from_midnight((self.state.edit.hour * 3600 + self.state.edit.minute * 60) as u32, 0),
-                                    });
-                                }
+                            let picked = match self.state.edit.add_time {
+                                true => self.state.edit.time.pick().map(Some),
+                                false => Ok(None),
+                            };
+                            let range = self.state.edit.active_range();
+                            let launcher = self.state.edit.launcher.pick();
+                            let taken = store::check_name(&self.plans.lock().unwrap(), &self.state.edit.plan.name, Some(self.state.edit.selection));
+                            if self.state.edit.plan.name == "" || self.state.edit.plan.link == "" {
+                                self.state.edit.output = format!("both name and link must be entered!");
+                            } else if let Err(e) = taken {
+                                self.state.edit.output = e;
+                            } else if let Err(e) = picked {
+                                self.state.edit.output = e;
+                            } else if let Err(e) = range {
+                                self.state.edit.output = e;
+                            } else if let Err(e) = launcher {
+                                self.state.edit.output = e;
+                            } else {
+                                self.state.edit.settings.launcher = launcher.unwrap();
+                                let (active_from, active_until) = range.unwrap();
+                                self.state.edit.settings.active_from = active_from;
+                                self.state.edit.settings.active_until = active_until;
+                                // removing first, so that the selected time still points at the right one
                                 if self.state.edit.remove_time {
-                                    self.state.edit.plan.times.remove(self.state.edit.selected_time);
+                                    utils::remove_time(&mut self.state.edit.plan, &mut self.state.edit.settings, self.state.edit.selected_time);
                                 }
+                                if let Ok(Some(picked)) = picked {
+                                    utils::add_time(&mut self.state.edit.plan, &mut self.state.edit.settings, picked);
+                                }
+                                self.state.edit.settings.catch_up = utils::usize_to_catch_up(self.state.edit.catch_up, self.state.edit.grace_minutes);
                                 let mut plans = self.plans.lock().unwrap();
+                                let old_name = plans[self.state.edit.selection].name.clone();
                                 plans.as_mut_slice()[self.state.edit.selection] = self.state.edit.plan.clone();
                                 drop(plans);
                                 let name = self.state.edit.plan.name.clone();
+                                self.settings.rename(&old_name, &name);
+                                self.state.edit.apply(self.settings.plans.entry(name.clone()).or_default());
                                 self.state.edit.refresh(0..self.plans.lock().unwrap().len());
                                 self.state.edit.output = format!("entry {} has been edited", name);
-                                if self.is_loop_running {
-                                    let _ = self.sender.send(false);
-                                    let _ = self.sender.send(true);
-                                }
-                            } else {
-                                self.state.edit.output = format!("both name and link must be entered!");
+                                self.reload_scheduler();
                             }
                         }
                         ui.add(egui::widgets::Label::new(self.state.edit.output.clone()).wrap(true));
@@ -221,80 +635,72 @@ impl epi::App for App {
                         if ui.button("remove").clicked() {
                             let mut plans = self.plans.lock().unwrap();
                             let Plan {name, .. } = plans.remove(self.state.remove.selection);
+                            drop(plans);
+                            self.settings.plans.remove(&name);
                             self.state.remove = RemoveUIState::default();
                             self.state.remove.output = format!("entry {} has been removed", name);
-                            if self.is_loop_running {
-                                let _ = self.sender.send(false);
-                                let _ = self.sender.send(true);
-                            }
+                            self.reload_scheduler();
                             ui.add(egui::widgets::Label::new(self.state.remove.output.clone()).wrap(true));
                         }
                     }
 
+                } else if self.state.section == 4 {
+                    self.week_section(ui);
+                } else if self.state.section == 5 {
+                    self.holidays_section(ui);
+                } else if self.state.section == 6 {
+                    self.history_section(ui);
+                } else if self.state.section == 7 {
+                    self.files_section(ui);
+                } else if self.state.section == 8 {
+                    self.entries_section(ui);
                 }
             });
         });
     }
 
     fn setup(&mut self, _ctx: &egui::CtxRef) {
-        let mut dir = home::home_dir().unwrap();
-        dir.push(".autolink");
-        let plans = autolink_lib::import(dir);
-        self.plans = Arc::new(Mutex::new(plans));
-        std::thread::spawn({
-            let plans = self.plans.clone();
-            let receiver = self.receiver.clone();
-            move || { 
-                let mut is_running = false;
-                let mut p = Vec::new();
-                let mut cp = Vec::new();
-                
-                loop {
-                    while !receiver.is_empty() {
-                        is_running = receiver.recv().unwrap();
-                        if is_running {
-                            let v = plans.lock().unwrap();
-                            p = Vec::from_iter(v.clone());
-                            drop(v); // explicitly dropping v preemptively, I'm using mutexes for the first time and I don't want to go crazy
-                            cp = Vec::from_iter(p.clone());
-                        }
-                    }
-                    if is_running {
-                        let day = chrono::Local::now().naive_local().date().weekday();
-                        let time = chrono::Local::now().naive_local().time();
-                        let time = chrono::NaiveTime::from_hms(time.hour(), time.minute(), 0);
-                        let timeday = TimeDay::new(time, day);
-                        let length = cp.len();
-                        for i in 0..length {
-                            if autolink_lib::check(cp.get(i).unwrap(), &timeday) {
-                                let mut p = cp.get(i).unwrap().clone();
-                                p.remove_matching_time(&timeday);
-                                cp.remove(i);    
-                                cp.push(p);
-                                break
-                            }
-                        }
-
-                        let mut times = 0;
-                        for p in &cp {
-                            times += p.times.len();
-                        }
-                        if times == 0 {
-                            cp = p.clone()
-                        }
-                        std::thread::sleep(std::time::Duration::new(5, 0));
-                    }
-                    std::thread::sleep(std::time::Duration::new(0, 0.25e4 as u32))
-                }
-            }
-        });
+        self.profile = crate::profiles::running();
+        match store::load(&self.profile) {
+            Ok(plans) => self.start(plans),
+            Err(e) => self.state.load_error = Some(e),
+        }
     }
 
     fn on_exit(&mut self) {
-        let mut dir = home::home_dir().unwrap();
-        dir.push(".autolink");
+        // nothing was started, and the file that couldn't be read is left as it is
+        if self.state.load_error.is_some() {
+            return
+        }
+        ipc::close();
+        // the profile being edited is saved, so that only the one the loop runs is left
+        // there is nobody left to tell but the terminal
+        if let Some(running) = self.running.take() {
+            self.take_loaded().close().unwrap_or_else(|e| eprintln!("{}", e));
+            self.put_loaded(running);
+        }
         let plans = self.plans.lock().unwrap().clone();
-        autolink_lib::export(plans, dir);
+        match self.writer.take() {
+            Some(writer) => {
+                writer.write(plans);
+                writer.finish();
+            },
+            None => store::save(&self.profile, plans).unwrap_or_else(|e| eprintln!("{}", e)),
+        }
+        for event in self.file_events.try_iter() {
+            if let FileEvent::Failed(e) = event {
+                eprintln!("{}", e);
+            }
+        }
+        let _ = self.sender.send(SchedulerCommand::Shutdown);
+        self.settings.checked_until = None;
+        while let Ok(event) = self.events.recv_timeout(std::time::Duration::from_secs(1)) {
+            if let SchedulerEvent::ShutDown { checked_until } = event {
+                self.settings.checked_until = checked_until;
+                break
+            }
+        }
+        self.settings.save(Settings::path(&self.profile)).unwrap_or_else(|e| eprintln!("{}", e));
     }
 }
 
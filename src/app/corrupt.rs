@@ -0,0 +1,41 @@
+use eframe::{egui, epi};
+use super::App;
+use crate::store;
+
+impl App {
+    /// Shown instead of everything else when ~/.autolink can't be read, nothing runs until it can.
+    pub(super) fn load_error_screen(&mut self, ctx: &egui::CtxRef, frame: &mut epi::Frame<'_>) {
+        egui::containers::CentralPanel::default().show(&ctx, |ui| {
+            ui.heading("the entries couldn't be read");
+            ui.add(egui::widgets::Label::new(self.state.load_error.clone().unwrap_or_default()).wrap(true));
+            ui.add(egui::widgets::Label::new(
+                "fix the file and try again, or start over with no entries, the file is then kept next to it"
+            ).wrap(true));
+            ui.add(egui::widgets::Separator::default().horizontal());
+            ui.horizontal(|ui| {
+                if ui.button("try again").clicked() {
+                    match store::load(&self.profile) {
+                        Ok(plans) => {
+                            self.state.load_error = None;
+                            self.start(plans);
+                        },
+                        Err(e) => self.state.load_error = Some(e),
+                    }
+                }
+                if ui.button("start over").clicked() {
+                    match store::set_aside(&self.profile) {
+                        Ok(aside) => {
+                            self.state.load_error = None;
+                            self.start(Vec::new());
+                            self.state.banner = Some(format!("started over, the old entries are in {}", aside.display()));
+                        },
+                        Err(e) => self.state.load_error = Some(format!("couldn't move {} away: {}", store::path(&self.profile).display(), e)),
+                    }
+                }
+                if ui.button("quit").clicked() {
+                    frame.quit();
+                }
+            });
+        });
+    }
+}
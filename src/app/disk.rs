@@ -0,0 +1,59 @@
+use eframe::egui;
+use crate::store;
+use super::App;
+use super::state::DiskChange;
+
+impl App {
+    /// Shown instead of the edit form when the entry in it was changed or removed on disk meanwhile.
+    pub(super) fn disk_change_section(&mut self, ui: &mut egui::Ui) {
+        let name = self.state.edit.original.as_ref().map(|plan| plan.name.clone()).unwrap_or_default();
+        match self.state.edit.disk_change {
+            Some(DiskChange::Changed) => {
+                ui.add(egui::widgets::Label::new(format!("{} was changed on disk while it was being edited", name)).wrap(true));
+                ui.horizontal(|ui| {
+                    if ui.button("load the new one").clicked() {
+                        let plan = self.plans.lock().unwrap()[self.state.edit.selection].clone();
+                        let settings = self.settings.plan(&plan.name);
+                        self.state.edit.load(plan, settings);
+                        self.state.edit.output = format!("loaded {} as it is on disk", name);
+                    }
+                    if ui.button("keep editing mine").clicked() {
+                        // editing it then writes over the one on disk
+                        self.state.edit.original = Some(self.plans.lock().unwrap()[self.state.edit.selection].clone());
+                        self.state.edit.disk_change = None;
+                    }
+                });
+            },
+            Some(DiskChange::Removed) => {
+                ui.add(egui::widgets::Label::new(format!("{} was removed on disk while it was being edited", name)).wrap(true));
+                ui.horizontal(|ui| {
+                    if ui.button("add mine back").clicked() {
+                        let plan = self.state.edit.plan.clone();
+                        let taken = store::check_name(&self.plans.lock().unwrap(), &plan.name, None);
+                        match taken {
+                            Ok(()) => {
+                                self.plans.lock().unwrap().push(plan.clone());
+                                self.state.edit.apply(self.settings.plans.entry(plan.name.clone()).or_default());
+                                self.forget_edit();
+                                self.state.edit.output = format!("entry {} has been added back", plan.name);
+                                self.reload_scheduler();
+                            },
+                            Err(e) => self.state.edit.output = e,
+                        }
+                    }
+                    if ui.button("drop mine").clicked() {
+                        self.forget_edit();
+                    }
+                });
+            },
+            None => (),
+        }
+        ui.add(egui::widgets::Label::new(self.state.edit.output.clone()).wrap(true));
+    }
+
+    /// Empties the edit form, for it to be filled in from the first entry.
+    fn forget_edit(&mut self) {
+        self.state.edit.selection = 0;
+        self.state.edit.reopen();
+    }
+}
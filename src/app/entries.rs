@@ -0,0 +1,62 @@
+use eframe::egui;
+use autolink_lib::chrono;
+use super::{App, utils};
+
+impl App {
+    /*
+     * every entry with its switches, a disabled entry launches nothing
+     * until it is enabled again, a paused one nothing until the day it is paused until
+    */
+    pub(super) fn entries_section(&mut self, ui: &mut egui::Ui) {
+        let plans = self.plans.lock().unwrap().clone();
+        if plans.len() == 0 {
+            ui.add(egui::widgets::Label::new("first of all, add some entries").wrap(true));
+            return
+        }
+        ui.horizontal(|ui| {
+            ui.add(egui::widgets::Label::new("pause until"));
+            utils::date_selection_box(ui, &mut self.state.entries.until);
+        });
+        ui.add(egui::widgets::Separator::default().horizontal());
+        let today = chrono::Local::today().naive_local();
+        let until = self.state.entries.until.date();
+        let mut changed = false;
+        egui::containers::ScrollArea::auto_sized().show(ui, |ui| {
+            egui::containers::Grid::new("entries").striped(true).show(ui, |ui| {
+                for plan in &plans {
+                    // a copy, so that entries nobody touched don't get settings written for them
+                    let mut settings = self.settings.plan(&plan.name);
+                    let mut toggled = false;
+                    ui.add(egui::widgets::Label::new(&plan.name).strong());
+                    toggled |= ui.add(egui::widgets::Checkbox::new(&mut settings.enabled, "enabled")).changed();
+                    match settings.paused_until.filter(|until| today < *until) {
+                        Some(paused_until) => {
+                            ui.add(egui::widgets::Label::new(format!("paused until {}", paused_until.format("%a %Y-%m-%d"))));
+                            if ui.small_button("resume").clicked() {
+                                settings.paused_until = None;
+                                toggled = true;
+                            }
+                        },
+                        None => {
+                            ui.add(egui::widgets::Label::new(""));
+                            if let Some(until) = until.filter(|until| today < *until) {
+                                if ui.small_button(format!("pause until {}", until.format("%a %d"))).clicked() {
+                                    settings.paused_until = Some(until);
+                                    toggled = true;
+                                }
+                            }
+                        },
+                    }
+                    if toggled {
+                        self.settings.plans.insert(plan.name.clone(), settings);
+                        changed = true;
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+        if changed {
+            self.reload_scheduler();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use eframe::egui;
+use std::path::PathBuf;
+use crate::exchange;
+use super::{App, utils};
+
+impl App {
+    /*
+     * entries are read from a file into a preview first,
+     * and only added once the user has seen what they are
+    */
+    pub(super) fn files_section(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::widgets::Label::new("read entries from a calendar or a spreadsheet, or write them to one, as a .ics or a .csv file").wrap(true));
+        ui.add(egui::widgets::TextEdit::singleline(&mut self.state.files.path).hint_text("path to the file"));
+        let path = PathBuf::from(self.state.files.path.trim());
+        ui.horizontal(|ui| {
+            if ui.button("read").clicked() {
+                match exchange::read(&path) {
+                    Ok(imported) => {
+                        self.state.files.output = format!("read {} entries, add them below", imported.entries.len());
+                        self.state.files.preview = Some(imported);
+                    },
+                    Err(e) => self.state.files.output = e,
+                }
+            }
+            if ui.button("write").clicked() {
+                let plans = self.plans.lock().unwrap().clone();
+                self.state.files.output = match exchange::write(&path, &plans, &self.settings) {
+                    Ok(warnings) => {
+                        let mut output = format!("wrote {} entries to {}", plans.len(), path.display());
+                        for warning in warnings {
+                            output = format!("{}\n{}", output, warning);
+                        }
+                        output
+                    },
+                    Err(e) => e,
+                };
+            }
+        });
+        ui.add(egui::widgets::Label::new(&self.state.files.output).wrap(true));
+
+        let imported = match &self.state.files.preview {
+            Some(imported) => imported,
+            None => return,
+        };
+        ui.add(egui::widgets::Separator::default().horizontal());
+        let mut answer = None;
+        egui::containers::ScrollArea::auto_sized().show(ui, |ui| {
+            for (plan, settings) in &imported.entries {
+                ui.group(|ui| {
+                    ui.add(egui::widgets::Label::new(format!("{} ({})", plan.name, plan.link)).strong().wrap(true));
+                    for label in utils::time_labels(plan, settings) {
+                        ui.add(egui::widgets::Label::new(label).small());
+                    }
+                });
+            }
+            for warning in &imported.warnings {
+                ui.colored_label(egui::Color32::YELLOW, warning);
+            }
+            if imported.entries.is_empty() {
+                ui.add(egui::widgets::Label::new("there is nothing to add").wrap(true));
+            }
+            ui.horizontal(|ui| {
+                if !imported.entries.is_empty() && ui.button("add them").clicked() {
+                    answer = Some(true);
+                }
+                if ui.button("cancel").clicked() {
+                    answer = Some(false);
+                }
+            });
+        });
+        match answer {
+            Some(true) => {
+                let imported = self.state.files.preview.take().unwrap();
+                let entries = imported.entries.len();
+                exchange::add(&mut self.plans.lock().unwrap(), &mut self.settings, imported.entries);
+                self.reload_scheduler();
+                self.state.files.output = format!("added {} entries", entries);
+            },
+            Some(false) => {
+                self.state.files.preview = None;
+                self.state.files.output = String::new();
+            },
+            None => (),
+        }
+    }
+}
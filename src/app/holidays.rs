@@ -0,0 +1,65 @@
+use eframe::egui;
+use autolink_lib::chrono::NaiveTime;
+use crate::settings::Quiet;
+use super::{App, utils};
+
+impl App {
+    /// Holidays and quiet times are for every entry, nothing is launched in them.
+    pub(super) fn holidays_section(&mut self, ui: &mut egui::Ui) {
+        ui.add(egui::widgets::Label::new("nothing is launched on these days").wrap(true));
+        ui.add(egui::widgets::Separator::default().horizontal());
+        if utils::date_list(ui, &mut self.settings.holidays, &mut self.state.holidays.date) {
+            self.reload_scheduler();
+        }
+        ui.add(egui::widgets::Separator::default().horizontal());
+        ui.add(egui::widgets::Label::new("nor at these times of the day").wrap(true));
+        ui.add(egui::widgets::Separator::default().horizontal());
+        let mut removed = None;
+        for (i, quiet) in self.settings.quiet.iter().enumerate() {
+            ui.horizontal( |ui| {
+                ui.add(egui::widgets::Label::new(quiet_label(quiet)));
+                if ui.button("remove").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.settings.quiet.remove(i);
+            self.reload_scheduler();
+        }
+        let state = &mut self.state.holidays;
+        ui.horizontal_wrapped( |ui| {
+            let days = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+            for (picked, day) in state.days.iter_mut().zip(days.iter()) {
+                ui.checkbox(picked, *day);
+            }
+        });
+        ui.horizontal( |ui| {
+            ui.add(egui::widgets::Label::new("from"));
+            ui.add(egui::widgets::DragValue::new(&mut state.from.0).clamp_range(0..=23));
+            ui.add(egui::widgets::DragValue::new(&mut state.from.1).clamp_range(0..=59));
+            ui.add(egui::widgets::Label::new("until"));
+            ui.add(egui::widgets::DragValue::new(&mut state.until.0).clamp_range(0..=23));
+            ui.add(egui::widgets::DragValue::new(&mut state.until.1).clamp_range(0..=59));
+        });
+        if state.from != state.until && ui.button("add quiet time").clicked() {
+            let quiet = Quiet {
+                days: (0..7).filter(|i| state.days[*i]).map(utils::usize_to_day).collect(),
+                from: NaiveTime::from_hms(state.from.0, state.from.1, 0),
+                until: NaiveTime::from_hms(state.until.0, state.until.1, 0),
+            };
+            if !self.settings.quiet.contains(&quiet) {
+                self.settings.quiet.push(quiet);
+                self.reload_scheduler();
+            }
+        }
+    }
+}
+
+fn quiet_label(quiet: &Quiet) -> String {
+    let days = match quiet.days.is_empty() {
+        true => String::from("every day"),
+        false => quiet.days.iter().map(|day| day.to_string()).collect::<Vec<_>>().join(", "),
+    };
+    format!("{} - {} to {}", days, quiet.from.format("%H:%M"), quiet.until.format("%H:%M"))
+}
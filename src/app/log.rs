@@ -0,0 +1,60 @@
+use eframe::egui;
+use crate::history::{self, Trigger};
+use super::App;
+
+impl App {
+    /*
+     * the history is read from disk when the section is opened
+     * or refreshed, newest launches are shown first
+    */
+    pub(super) fn history_section(&mut self, ui: &mut egui::Ui) {
+        let state = &mut self.state.history;
+        if state.records.is_none() || ui.button("refresh").clicked() {
+            state.records = Some(history::read(&history::path()));
+        }
+        ui.add(egui::widgets::TextEdit::singleline(&mut state.filter).hint_text("filter by name or link"));
+        let triggers = ["any", "schedule", "catch up", "manual"];
+        egui::containers::ComboBox::from_label("launched by").show_index(ui, &mut state.trigger, triggers.len(), |i| {
+            String::from(triggers[i])
+        });
+        ui.add(egui::widgets::Checkbox::new(&mut state.failures_only, "failures only?"));
+        ui.add(egui::widgets::Separator::default().horizontal());
+
+        let trigger = match state.trigger {
+            1 => Some(Trigger::Schedule),
+            2 => Some(Trigger::CatchUp),
+            3 => Some(Trigger::Manual),
+            _ => None,
+        };
+        let filter = state.filter.to_lowercase();
+        let records: Vec<_> = state.records.as_ref().unwrap().iter().rev()
+            .filter(|record| trigger.map_or(true, |trigger| record.trigger == trigger))
+            .filter(|record| !state.failures_only || record.error.is_some())
+            .filter(|record| record.name.to_lowercase().contains(&filter) || record.link.to_lowercase().contains(&filter))
+            .collect();
+        if records.len() == 0 {
+            ui.add(egui::widgets::Label::new("nothing has been launched yet").wrap(true));
+            return
+        }
+        egui::containers::ScrollArea::auto_sized().show(ui, |ui| {
+            egui::containers::Grid::new("history").striped(true).show(ui, |ui| {
+                for record in records {
+                    ui.add(egui::widgets::Label::new(record.at.format("%Y-%m-%d %H:%M")));
+                    ui.add(egui::widgets::Label::new(&record.name));
+                    ui.add(egui::widgets::Label::new(record.trigger.name()));
+                    match (&record.error, record.status) {
+                        (Some(e), _) => ui.colored_label(egui::Color32::RED, format!("failed: {}", e)),
+                        (None, Some(status)) => ui.add(egui::widgets::Label::new(format!("opened, exited with {}", status))),
+                        (None, None) => ui.add(egui::widgets::Label::new("opened")),
+                    };
+                    ui.add(egui::widgets::Label::new(&record.link));
+                    match &record.output {
+                        Some(output) => ui.add(egui::widgets::Label::new("output")).on_hover_text(output),
+                        None => ui.add(egui::widgets::Label::new("")),
+                    };
+                    ui.end_row();
+                }
+            });
+        });
+    }
+}
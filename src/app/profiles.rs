@@ -0,0 +1,180 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use autolink_lib::Plan;
+use crossbeam_channel::Receiver;
+use crate::profiles;
+use crate::scheduler::SchedulerCommand;
+use crate::settings::Settings;
+use crate::store::{self, FileEvent};
+use super::App;
+
+/// The plans and settings of a profile, with the writer that saves them.
+pub(super) struct Loaded {
+    pub name: String,
+    pub plans: Arc<Mutex<Vec<Plan>>>,
+    pub settings: Settings,
+    pub writer: Option<store::Writer>,
+    pub file_events: Receiver<FileEvent>,
+}
+
+impl Loaded {
+    /// The writer only hands changes on disk to the loop when it is given where to send them.
+    pub fn open(name: &str, plans: Vec<Plan>, commands: Option<crossbeam_channel::Sender<SchedulerCommand>>) -> Self {
+        let plans = Arc::new(Mutex::new(plans));
+        let (writer, file_events) = store::Writer::spawn(name, plans.clone(), commands);
+        Self {
+            name: name.to_string(),
+            plans,
+            settings: Settings::load(Settings::path(name)),
+            writer: Some(writer),
+            file_events,
+        }
+    }
+
+    /// Saves everything and stops the writer, returns why something couldn't be saved.
+    pub fn close(mut self) -> Result<(), String> {
+        match self.writer.take() {
+            Some(writer) => {
+                writer.write(self.plans.lock().unwrap().clone());
+                writer.write_settings(self.settings.clone());
+                writer.finish();
+            },
+            None => self.settings.save(Settings::path(&self.name))?,
+        }
+        let failed = self.file_events.try_iter()
+            .filter_map(|event| match event {
+                FileEvent::Failed(e) => Some(e),
+                _ => None,
+            })
+            .last();
+        failed.map_or(Ok(()), Err)
+    }
+}
+
+/*
+ * the app edits one profile while the loop runs one, usually the same
+ * 
+ * the plans and settings of the app are always those of the profile being edited,
+ * when that isn't the one the loop runs, the one the loop runs is kept aside in `running`
+*/
+impl App {
+    pub(super) fn running_profile(&self) -> &str {
+        match &self.running {
+            Some(running) => &running.name,
+            None => &self.profile,
+        }
+    }
+
+    pub(super) fn take_loaded(&mut self) -> Loaded {
+        Loaded {
+            name: std::mem::take(&mut self.profile),
+            plans: std::mem::take(&mut self.plans),
+            settings: std::mem::take(&mut self.settings),
+            writer: self.writer.take(),
+            file_events: std::mem::replace(&mut self.file_events, crossbeam_channel::never()),
+        }
+    }
+
+    pub(super) fn put_loaded(&mut self, loaded: Loaded) {
+        self.profile = loaded.name;
+        self.plans = loaded.plans;
+        self.settings = loaded.settings;
+        self.writer = loaded.writer;
+        self.file_events = loaded.file_events;
+        self.state.start.upcoming_at = None;
+    }
+
+    /// Switches to editing another profile, the loop keeps running its own.
+    pub(super) fn edit_profile(&mut self, name: &str) -> Result<(), String> {
+        if name == self.profile {
+            return Ok(())
+        }
+        let other = match name == self.running_profile() {
+            true => None,
+            false => Some(Loaded::open(name, store::load(name)?, None)),
+        };
+        let current = self.take_loaded();
+        match self.running {
+            None => self.running = Some(current),
+            Some(_) => if let Err(e) = current.close() {
+                self.state.banner = Some(e);
+            },
+        }
+        let next = other.unwrap_or_else(|| self.running.take().unwrap());
+        self.put_loaded(next);
+        self.state.set_sections_to_default();
+        Ok(())
+    }
+
+    /// Has the loop run the profile being edited, instead of the one it ran.
+    pub(super) fn run_profile(&mut self) {
+        let mut previous = match self.running.take() {
+            Some(previous) => previous,
+            None => return,
+        };
+        // the pause is the loop's, it goes along to the profile it runs now
+        let paused_until = previous.settings.paused_until.take();
+        if let Err(e) = previous.close() {
+            self.state.banner = Some(e);
+        }
+        // the writer has to tell the loop about changes on disk from now on
+        let loaded = self.take_loaded();
+        let name = loaded.name.clone();
+        let plans = loaded.plans.lock().unwrap().clone();
+        if let Err(e) = loaded.close() {
+            self.state.banner = Some(e);
+        }
+        self.put_loaded(Loaded::open(&name, plans, Some(self.sender.clone())));
+        self.settings.paused_until = paused_until;
+        *self.ipc_target.lock().unwrap() = self.plans.clone();
+        profiles::set_running(&self.profile);
+        // the missed launches point at entries of the other profile
+        self.state.status.missed.clear();
+        self.reload_scheduler();
+    }
+
+    pub(super) fn profile_bar(&mut self, ui: &mut egui::Ui) {
+        let mut chosen = None;
+        ui.horizontal_wrapped(|ui| {
+            let running = self.running_profile().to_string();
+            let label = |name: &str| match name == running {
+                true => format!("{} (running)", name),
+                false => name.to_string(),
+            };
+            egui::containers::ComboBox::from_label("profile").selected_text(label(&self.profile)).show_ui(ui, |ui| {
+                for name in &self.state.profiles.names {
+                    if ui.selectable_label(*name == self.profile, label(name)).clicked() {
+                        chosen = Some(name.clone());
+                    }
+                }
+            });
+            if self.running.is_some() {
+                ui.add(egui::widgets::Label::new(format!("the loop runs {}", running)).small());
+                if ui.small_button("run this one").clicked() {
+                    self.run_profile();
+                }
+            }
+            ui.add(egui::widgets::TextEdit::singleline(&mut self.state.profiles.new_name).hint_text("new profile").desired_width(100.0));
+            if ui.small_button("make").clicked() {
+                let name = self.state.profiles.new_name.trim().to_string();
+                match profiles::create(&name) {
+                    Ok(()) => {
+                        self.state.profiles.names = profiles::list();
+                        self.state.profiles.new_name = String::new();
+                        chosen = Some(name);
+                    },
+                    Err(e) => self.state.profiles.output = e,
+                }
+            }
+            if !self.state.profiles.output.is_empty() {
+                ui.add(egui::widgets::Label::new(&self.state.profiles.output).small());
+            }
+        });
+        if let Some(name) = chosen {
+            self.state.profiles.output = match self.edit_profile(&name) {
+                Ok(()) => String::new(),
+                Err(e) => e,
+            };
+        }
+    }
+}
@@ -1,4 +1,11 @@
-use autolink_lib::Plan;
+use autolink_lib::{Plan, TimeDay};
+use autolink_lib::chrono;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use crate::settings::{PlanSettings, Action, ActionKind, Launcher, Once, Program, Rule, Repeat};
+use crate::history::Record;
+use crate::exchange::Imported;
+use crate::{launch, store};
+use super::utils;
 /*
  * since egui renders in immediate mode, the ui has to keep its own state
  * this struct is where those variables will live
@@ -9,10 +16,23 @@ use autolink_lib::Plan;
 pub struct UIState {
     pub section: usize,
     pub prev_section: usize,
+    pub status: LoopStatus,
+    /// Says when ~/.autolink was changed by something else, until it is dismissed.
+    pub banner: Option<String>,
+    /// Why ~/.autolink couldn't be read, the app waits on the user while it is set.
+    pub load_error: Option<String>,
+    /// The day the loop is paused until, tomorrow unless picked.
+    pub pause: DateSelection,
     pub start: StartUIState,
     pub add: AddUIState,
     pub edit: EditUIState,
     pub remove: RemoveUIState,
+    pub holidays: HolidaysUIState,
+    pub history: HistoryUIState,
+    pub files: FilesUIState,
+    pub entries: EntriesUIState,
+    pub week: WeekUIState,
+    pub profiles: ProfilesUIState,
 }
 
 impl Default for UIState {
@@ -20,10 +40,20 @@ impl Default for UIState {
         Self {
             section: 0,
             prev_section: 0,
+            status: LoopStatus::default(),
+            banner: None,
+            load_error: None,
+            pause: DateSelection::from(chrono::Local::today().naive_local().succ()),
             start: StartUIState::default(),
             add: AddUIState::default(),
             edit: EditUIState::default(),
             remove: RemoveUIState::default(),
+            holidays: HolidaysUIState::default(),
+            history: HistoryUIState::default(),
+            files: FilesUIState::default(),
+            entries: EntriesUIState::default(),
+            week: WeekUIState::default(),
+            profiles: ProfilesUIState::default(),
         }
     }
 }
@@ -34,17 +64,87 @@ impl UIState {
         self.add = AddUIState::default();
         self.edit = EditUIState::default();
         self.remove = RemoveUIState::default();
+        self.holidays = HolidaysUIState::default();
+        self.history = HistoryUIState::default();
+        self.files = FilesUIState::default();
+        self.entries = EntriesUIState::default();
+        self.week = WeekUIState::default();
+    }
+
+    /// Unselects entries which don't exist anymore.
+    pub fn clamp_selections(&mut self, entries: usize) {
+        if self.start.selection >= entries {
+            self.start.selection = 0;
+        }
+        if self.edit.selection >= entries {
+            self.edit = EditUIState::default();
+        }
+        if self.remove.selection >= entries {
+            self.remove.selection = 0;
+        }
+    }
+
+    /// Switches to the edit section with the given entry and time selected.
+    pub fn edit_time(&mut self, selection: usize, selected_time: usize) {
+        self.section = 2;
+        self.prev_section = 2;
+        self.set_sections_to_default();
+        self.edit.selection = selection;
+        self.edit.reopen();
+        self.edit.selected_time = selected_time;
+    }
+}
+
+/*
+ * the ui doesn't know what the loop thread is doing,
+ * it only knows what the loop thread has told it
+*/
+pub struct LoopStatus {
+    pub is_running: bool,
+    pub entries: usize,
+    pub paused_until: Option<NaiveDateTime>,
+    pub last_launch: Option<(String, NaiveDateTime)>,
+    /// Launches that were missed and are waiting for the user, with their plan as it was then.
+    pub missed: Vec<(Plan, NaiveDateTime)>,
+    pub notices: Vec<String>,
+}
+
+impl LoopStatus {
+    pub fn notice(&mut self, notice: String) {
+        self.notices.push(notice);
+        if self.notices.len() > 5 {
+            self.notices.remove(0);
+        }
+    }
+}
+
+impl Default for LoopStatus {
+    fn default() -> Self {
+        Self {
+            is_running: false,
+            entries: 0,
+            paused_until: None,
+            last_launch: None,
+            missed: Vec::new(),
+            notices: Vec::new(),
+        }
     }
 }
 
 pub struct StartUIState {
     pub selection: usize,
+    /// The "next up" list, worked out again each minute or when the plans change.
+    pub upcoming: Vec<(usize, NaiveDateTime)>,
+    /// The minute the list was worked out in, None once it is out of date.
+    pub upcoming_at: Option<NaiveDateTime>,
 }
 
 impl Default for StartUIState {
     fn default() -> Self {
         Self {
             selection: 0,
+            upcoming: Vec::new(),
+            upcoming_at: None,
         }
     }
 }
@@ -52,10 +152,11 @@ impl Default for StartUIState {
 pub struct AddUIState {
     pub name: String,
     pub link: String,
+    pub actions: Vec<Action>,
+    pub action: ActionSelection,
+    pub launcher: LauncherSelection,
     pub add_time: bool,
-    pub selected_day: usize,
-    pub hour: usize,
-    pub minute: usize,
+    pub time: TimeSelection,
     pub output: String,
 }
 
@@ -64,11 +165,272 @@ impl Default for AddUIState {
         Self {
             name: String::new(),
             link: String::new(),
+            actions: Vec::new(),
+            action: ActionSelection::default(),
+            launcher: LauncherSelection::default(),
             add_time: false,
+            time: TimeSelection::default(),
+            output: String::new(),
+        }
+    }
+}
+
+/*
+ * both the add and the edit section let the user add actions,
+ * which either open a link or run a program, and change those added before
+*/
+pub struct ActionSelection {
+    pub is_command: bool,
+    pub url: String,
+    pub program: String,
+    /// Quoted like in a shell.
+    pub args: String,
+    pub dir: String,
+    /// Like `KEY=value OTHER="some value"`.
+    pub env: String,
+    pub timeout_secs: u64,
+    pub delay_secs: u64,
+    /// The index of the action being changed, None while adding one.
+    pub editing: Option<usize>,
+}
+
+impl ActionSelection {
+    pub fn pick(&self) -> Result<Action, String> {
+        let kind = match self.is_command {
+            false if self.url.trim() == "" => return Err(String::from("the link must be entered!")),
+            false => ActionKind::Link { url: self.url.trim().to_string() },
+            true if self.program.trim() == "" => return Err(String::from("the program must be entered!")),
+            true => {
+                let mut env = Vec::new();
+                for pair in launch::split(&self.env).map_err(|e| format!("{} in the environment!", e))? {
+                    // the value may have = in it too
+                    match pair.find('=') {
+                        Some(i) if i > 0 => env.push((pair[..i].to_string(), pair[i + 1..].to_string())),
+                        _ => return Err(format!("{} must be like KEY=value!", pair)),
+                    }
+                }
+                ActionKind::Command(Program {
+                    program: self.program.trim().to_string(),
+                    args: launch::split(&self.args).map_err(|e| format!("{} in the arguments!", e))?,
+                    dir: Some(self.dir.trim().to_string()).filter(|dir| dir != ""),
+                    env,
+                    timeout_secs: self.timeout_secs,
+                })
+            },
+        };
+        Ok(Action { delay_secs: self.delay_secs, kind })
+    }
+
+    /// Fills the fields in from an action, to change it.
+    pub fn edit(i: usize, action: &Action) -> Self {
+        let mut selection = Self {
+            delay_secs: action.delay_secs,
+            editing: Some(i),
+            ..Self::default()
+        };
+        match &action.kind {
+            ActionKind::Link { url } => selection.url = url.clone(),
+            ActionKind::Command(program) => {
+                selection.is_command = true;
+                selection.program = program.program.clone();
+                selection.args = launch::join(&program.args);
+                selection.dir = program.dir.clone().unwrap_or_default();
+                let env: Vec<String> = program.env.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+                selection.env = launch::join(&env);
+                selection.timeout_secs = program.timeout_secs;
+            },
+        }
+        selection
+    }
+}
+
+impl Default for ActionSelection {
+    fn default() -> Self {
+        Self {
+            is_command: false,
+            url: String::new(),
+            program: String::new(),
+            args: String::new(),
+            dir: String::new(),
+            env: String::new(),
+            timeout_secs: Program::default().timeout_secs,
+            delay_secs: 0,
+            editing: None,
+        }
+    }
+}
+
+/*
+ * both the add and the edit section let the user pick what the links of an entry
+ * are opened with, the default browser, another browser or a command
+*/
+pub struct LauncherSelection {
+    /// As in `utils::launcher_selection_box`.
+    pub selected: usize,
+    pub program: String,
+    /// The arguments given to the browser, quoted like in a shell.
+    pub args: String,
+    pub template: String,
+}
+
+impl LauncherSelection {
+    /// What the links are to be opened with as picked, if enough of it was entered.
+    pub fn pick(&self) -> Result<Launcher, String> {
+        match self.selected {
+            1 if self.program.trim() == "" => Err(String::from("the browser must be entered!")),
+            1 => Ok(Launcher::Browser {
+                program: self.program.trim().to_string(),
+                args: launch::split(&self.args).map_err(|e| format!("{} in the arguments!", e))?,
+            }),
+            2 if !self.template.contains("{link}") => Err(String::from("the command must have {link} in it!")),
+            2 => match launch::split(&self.template) {
+                Ok(_) => Ok(Launcher::Command { template: self.template.trim().to_string() }),
+                Err(e) => Err(format!("{} in the command!", e)),
+            },
+            _ => Ok(Launcher::System),
+        }
+    }
+}
+
+impl From<&Launcher> for LauncherSelection {
+    fn from(launcher: &Launcher) -> Self {
+        match launcher {
+            Launcher::System => Self::default(),
+            Launcher::Browser { program, args } => Self {
+                selected: 1,
+                program: program.clone(),
+                args: launch::join(args),
+                ..Self::default()
+            },
+            Launcher::Command { template } => Self {
+                selected: 2,
+                template: template.clone(),
+                ..Self::default()
+            },
+        }
+    }
+}
+
+impl Default for LauncherSelection {
+    fn default() -> Self {
+        Self {
+            selected: 0,
+            program: String::new(),
+            args: String::new(),
+            template: String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimeKind {
+    Weekly,
+    Once,
+    Repeat,
+}
+
+/*
+ * both the add and the edit section let the user pick a time,
+ * which is either a weekly time, a date or a repeat
+*/
+pub struct TimeSelection {
+    pub kind: TimeKind,
+    pub selected_day: usize,
+    pub hour: usize,
+    pub minute: usize,
+    /// The date of a launch that happens once, or where an every n weeks repeat starts counting.
+    pub date: DateSelection,
+    pub repeat: usize,
+    pub weeks: u32,
+    /// Which of the days in a month, counting from 0, where 4 is the last one.
+    pub nth: usize,
+}
+
+/// A time picked by the user, ready to be added to an entry.
+pub enum PickedTime {
+    Weekly(TimeDay),
+    Once(Once),
+    Rule(Rule),
+}
+
+impl TimeSelection {
+    pub fn time(&self) -> NaiveTime {
+        NaiveTime::from_num_seconds_from_midnight((self.hour * 3600 + self.minute * 60) as u32, 0)
+    }
+
+    pub fn pick(&self) -> Result<PickedTime, String> {
+        let day = utils::usize_to_day(self.selected_day);
+        match self.kind {
+            TimeKind::Weekly => Ok(PickedTime::Weekly(TimeDay { day, time: self.time() })),
+            TimeKind::Once => {
+                let at = self.date.date().map(|date| date.and_time(self.time()));
+                match utils::is_ahead(at) {
+                    true => Ok(PickedTime::Once(Once::new(at.unwrap()))),
+                    false => Err(String::from("the date must exist and be ahead of now!")),
+                }
+            },
+            TimeKind::Repeat => {
+                let repeat = match self.repeat {
+                    0 => Repeat::Daily,
+                    1 => Repeat::Weekdays,
+                    2 => match self.date.date() {
+                        Some(anchor) => Repeat::EveryNWeeks { weeks: self.weeks, day, anchor },
+                        None => return Err(String::from("the starting date must exist!")),
+                    },
+                    3 => Repeat::MonthlyNth { nth: self.nth as u32 + 1, day },
+                    _ => unreachable!(),
+                };
+                Ok(PickedTime::Rule(Rule { repeat, time: self.time() }))
+            },
+        }
+    }
+}
+
+impl Default for TimeSelection {
+    fn default() -> Self {
+        Self {
+            kind: TimeKind::Weekly,
             selected_day: 0,
             hour: 0,
             minute: 0,
-            output: String::new(),
+            date: DateSelection::default(),
+            repeat: 0,
+            weeks: 2,
+            nth: 0,
+        }
+    }
+}
+
+/// A date being picked, it may not exist while the user is still picking.
+pub struct DateSelection {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl DateSelection {
+    pub fn date(&self) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(self.year, self.month, self.day)
+    }
+}
+
+impl From<NaiveDate> for DateSelection {
+    fn from(date: NaiveDate) -> Self {
+        Self {
+            year: date.year(),
+            month: date.month(),
+            day: date.day(),
+        }
+    }
+}
+
+impl Default for DateSelection {
+    fn default() -> Self {
+        let today = chrono::Local::today();
+        Self {
+            year: today.year(),
+            month: today.month(),
+            day: today.day(),
         }
     }
 }
@@ -77,16 +439,103 @@ pub struct EditUIState {
     pub selection: usize,
     pub prev_selection: usize,
     pub plan: Plan,
+    pub settings: PlanSettings,
     pub add_time: bool,
-    pub selected_day: usize,
-    pub hour: usize,
-    pub minute: usize,
+    pub time: TimeSelection,
     pub remove_time: bool,
     pub selected_time: usize,
+    pub catch_up: usize,
+    pub grace_minutes: i64,
+    pub action: ActionSelection,
+    pub launcher: LauncherSelection,
+    pub has_from: bool,
+    pub from: DateSelection,
+    pub has_until: bool,
+    pub until: DateSelection,
+    pub skip: DateSelection,
     pub output: String,
+    /// The entry as it was when the form was filled in, None until it is.
+    pub original: Option<Plan>,
+    /// Set when that entry changed on disk, until the user picks what to do about it.
+    pub disk_change: Option<DiskChange>,
+}
+
+/// What happened on disk to the entry being edited.
+pub enum DiskChange {
+    Changed,
+    Removed,
 }
 
 impl EditUIState {
+    /// Fills the fields in from the selected entry.
+    pub fn load(&mut self, plan: Plan, settings: PlanSettings) {
+        let (catch_up, grace_minutes) = utils::catch_up_to_usize(settings.catch_up);
+        self.catch_up = catch_up;
+        self.grace_minutes = grace_minutes;
+        self.launcher = LauncherSelection::from(&settings.launcher);
+        self.has_from = settings.active_from.is_some();
+        self.from = settings.active_from.map(DateSelection::from).unwrap_or_default();
+        self.has_until = settings.active_until.is_some();
+        self.until = settings.active_until.map(DateSelection::from).unwrap_or_default();
+        self.original = Some(plan.clone());
+        self.disk_change = None;
+        self.plan = plan;
+        self.settings = settings;
+    }
+
+    /// Keeps the form on the entry it was filled in from after the plans changed under it,
+    /// and asks what to do if that entry was changed or removed.
+    pub fn follow(&mut self, plans: &[Plan]) {
+        let original = match &self.original {
+            Some(original) => original,
+            None => return,
+        };
+        match plans.iter().position(|plan| plan.name == original.name) {
+            Some(i) => {
+                self.selection = i;
+                self.prev_selection = i;
+                if !store::same_plan(&plans[i], original) {
+                    self.disk_change = Some(DiskChange::Changed);
+                }
+            },
+            None => self.disk_change = Some(DiskChange::Removed),
+        }
+    }
+
+    /// The first and the last day of the entry as picked, if they exist and are in order.
+    pub fn active_range(&self) -> Result<(Option<NaiveDate>, Option<NaiveDate>), String> {
+        let from = match self.has_from {
+            true => Some(self.from.date().ok_or(String::from("the starting date must exist!"))?),
+            false => None,
+        };
+        let until = match self.has_until {
+            true => Some(self.until.date().ok_or(String::from("the ending date must exist!"))?),
+            false => None,
+        };
+        match (from, until) {
+            (Some(from), Some(until)) if until < from => Err(String::from("the entry must start before it ends!")),
+            _ => Ok((from, until)),
+        }
+    }
+
+    /// Writes what the form changes over the settings of the entry, keeping whatever else
+    /// changed while it was open, like launches on a date being done or the entry being paused.
+    pub fn apply(&self, settings: &mut PlanSettings) {
+        settings.actions = self.settings.actions.clone();
+        settings.launcher = self.settings.launcher.clone();
+        settings.catch_up = self.settings.catch_up;
+        settings.once = self.settings.once.iter()
+            .map(|once| Once {
+                at: once.at,
+                done: once.done || settings.once.iter().any(|live| live.at == once.at && live.done),
+            })
+            .collect();
+        settings.rules = self.settings.rules.clone();
+        settings.active_from = self.settings.active_from;
+        settings.active_until = self.settings.active_until;
+        settings.skipped = self.settings.skipped.clone();
+    }
+
     pub fn refresh(&mut self, r: std::ops::Range<usize>) {
         let mut new = Self::default();
         new.selection = self.selection;
@@ -94,6 +543,16 @@ impl EditUIState {
         *self = new;
     }
 
+    /// Empties the form for the selected entry to be loaded into it on the next frame,
+    /// which unlike `refresh` also works when there is only one entry.
+    pub fn reopen(&mut self) {
+        *self = Self {
+            selection: self.selection,
+            prev_selection: usize::MAX,
+            ..Self::default()
+        };
+    }
+
     fn prev_selection_generate(r: std::ops::Range<usize>, not: usize) -> usize {
         let mut prev_sel = 0;
         for i in r {
@@ -114,13 +573,23 @@ impl Default for EditUIState {
             selection: 0,
             prev_selection: 1, // this is for the update() function to assign a valid plan to the "plan" field
             plan: Plan { name: String::new(), link: String::new(), times: vec![] },
+            settings: PlanSettings::default(),
             add_time: false,
-            selected_day: 0,
-            hour: 0,
-            minute: 0,
+            time: TimeSelection::default(),
             remove_time: false,
             selected_time: 0,
+            catch_up: 0,
+            grace_minutes: 15,
+            action: ActionSelection::default(),
+            launcher: LauncherSelection::default(),
+            has_from: false,
+            from: DateSelection::default(),
+            has_until: false,
+            until: DateSelection::default(),
+            skip: DateSelection::default(),
             output: String::new(),
+            original: None,
+            disk_change: None,
         }
     }
 }
@@ -139,6 +608,104 @@ impl Default for RemoveUIState {
     }
 }
 
+pub struct HolidaysUIState {
+    pub date: DateSelection,
+    /// The quiet time being made, `days` starts on Monday and none picked means every day.
+    pub days: [bool; 7],
+    pub from: (u32, u32),
+    pub until: (u32, u32),
+}
+
+impl Default for HolidaysUIState {
+    fn default() -> Self {
+        Self {
+            date: DateSelection::default(),
+            days: [false; 7],
+            from: (22, 0),
+            until: (7, 0),
+        }
+    }
+}
+
+pub struct HistoryUIState {
+    /// Read from disk when the section is opened.
+    pub records: Option<Vec<Record>>,
+    pub filter: String,
+    pub trigger: usize,
+    pub failures_only: bool,
+}
+
+impl Default for HistoryUIState {
+    fn default() -> Self {
+        Self {
+            records: None,
+            filter: String::new(),
+            trigger: 0,
+            failures_only: false,
+        }
+    }
+}
+
+pub struct FilesUIState {
+    pub path: String,
+    /// What was read, waiting for the user to add it.
+    pub preview: Option<Imported>,
+    pub output: String,
+}
+
+impl Default for FilesUIState {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            preview: None,
+            output: String::new(),
+        }
+    }
+}
+
+pub struct EntriesUIState {
+    /// The day entries are paused until, a week from today unless picked.
+    pub until: DateSelection,
+}
+
+impl Default for EntriesUIState {
+    fn default() -> Self {
+        Self {
+            until: DateSelection::from(chrono::Local::today().naive_local() + chrono::Duration::days(7)),
+        }
+    }
+}
+
+pub struct WeekUIState {
+    /// The week on screen, in weeks from this one.
+    pub offset: i64,
+}
+
+impl Default for WeekUIState {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+        }
+    }
+}
+
+/// The profile switcher is above every section, so it isn't reset with them.
+pub struct ProfilesUIState {
+    pub names: Vec<String>,
+    pub new_name: String,
+    pub output: String,
+}
+
+impl Default for ProfilesUIState {
+    fn default() -> Self {
+        Self {
+            names: Vec::new(),
+            new_name: String::new(),
+            output: String::new(),
+        }
+    }
+}
+
 /*
  * WRITE CHECKS TO PREVENT CRASHES IF "PLANS" IS EMPTY
  * WRITE THE REMOVE UI AND LOGIC
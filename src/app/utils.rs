@@ -1,7 +1,9 @@
 use eframe::egui;
 use eframe::egui::Ui;
-use crate::app::state::UIState;
-use autolink_lib::{Plan, chrono};
+use crate::app::state::{ActionSelection, DateSelection, LauncherSelection, TimeSelection, TimeKind, PickedTime};
+use autolink_lib::{Plan, TimeDay, chrono};
+use chrono::Datelike;
+use crate::settings::{PlanSettings, Action, ActionKind, CatchUp, Once, Rule};
 use std::sync::{Arc, Mutex};
 
 pub fn entry_selection_box(ui: &mut Ui, plans: Arc<Mutex<Vec<Plan>>>, selected: &mut usize) {
@@ -18,6 +20,234 @@ pub fn day_selection_box(ui: &mut Ui, selected_day: &mut usize) {
     });
 }
 
+pub fn time_selection_box(ui: &mut Ui, selection: &mut TimeSelection) {
+    ui.horizontal( |ui| {
+        ui.radio_value(&mut selection.kind, TimeKind::Weekly, "every week");
+        ui.radio_value(&mut selection.kind, TimeKind::Once, "once");
+        ui.radio_value(&mut selection.kind, TimeKind::Repeat, "repeat");
+    });
+    match selection.kind {
+        TimeKind::Weekly => day_selection_box(ui, &mut selection.selected_day),
+        TimeKind::Once => date_selection_box(ui, &mut selection.date),
+        TimeKind::Repeat => {
+            let repeats = ["daily", "on weekdays", "every few weeks", "monthly"];
+            egui::containers::ComboBox::from_label("repeat").show_index(ui, &mut selection.repeat, repeats.len(), |i| {
+                String::from(repeats[i])
+            });
+            if selection.repeat == 2 {
+                day_selection_box(ui, &mut selection.selected_day);
+                ui.horizontal( |ui| {
+                    ui.add(egui::widgets::Label::new("every").wrap(true));
+                    ui.add(egui::widgets::DragValue::new(&mut selection.weeks).clamp_range(1..=52));
+                    ui.add(egui::widgets::Label::new("weeks, counting from the week of").wrap(true));
+                });
+                date_selection_box(ui, &mut selection.date);
+            } else if selection.repeat == 3 {
+                let nths = ["first", "second", "third", "fourth", "last"];
+                egui::containers::ComboBox::from_label("of the month").show_index(ui, &mut selection.nth, nths.len(), |i| {
+                    String::from(nths[i])
+                });
+                day_selection_box(ui, &mut selection.selected_day);
+            }
+        },
+    }
+    ui.horizontal( |ui| {
+        ui.add(egui::widgets::DragValue::new(&mut selection.hour).clamp_range(0..=23));
+        ui.add(egui::widgets::Label::new("hour").wrap(true));
+        ui.add(egui::widgets::DragValue::new(&mut selection.minute).clamp_range(0..=59));
+        ui.add(egui::widgets::Label::new("minute").wrap(true));
+    });
+}
+
+pub fn date_selection_box(ui: &mut Ui, date: &mut DateSelection) {
+    ui.horizontal( |ui| {
+        ui.add(egui::widgets::DragValue::new(&mut date.year).clamp_range(2000..=2100));
+        ui.add(egui::widgets::Label::new("year").wrap(true));
+        ui.add(egui::widgets::DragValue::new(&mut date.month).clamp_range(1..=12));
+        ui.add(egui::widgets::Label::new("month").wrap(true));
+        ui.add(egui::widgets::DragValue::new(&mut date.day).clamp_range(1..=31));
+        ui.add(egui::widgets::Label::new("day").wrap(true));
+    });
+    ui.add(egui::widgets::Label::new(match date.date() {
+        Some(date) => format!("a {}", date.format("%A")),
+        None => String::from("that date doesn't exist"),
+    }).wrap(true));
+}
+
+/// Whether a date and time picked for a launch exists and hasn't passed yet.
+pub fn is_ahead(at: Option<chrono::NaiveDateTime>) -> bool {
+    match at {
+        Some(at) => at > chrono::Local::now().naive_local(),
+        None => false,
+    }
+}
+
+/// The labels of every time of an entry, weekly times first, then dates, then repeats.
+pub fn time_labels(plan: &Plan, settings: &PlanSettings) -> Vec<String> {
+    let mut labels: Vec<String> = plan.times.iter()
+        .map(|TimeDay { day, time }| format!("{} - {}", day, time))
+        .collect();
+    labels.extend(settings.once.iter().map(Once::label));
+    labels.extend(settings.rules.iter().map(Rule::label));
+    labels
+}
+
+pub fn add_time(plan: &mut Plan, settings: &mut PlanSettings, picked: PickedTime) {
+    match picked {
+        PickedTime::Weekly(timeday) => plan.times.push(timeday),
+        PickedTime::Once(once) => settings.once.push(once),
+        PickedTime::Rule(rule) => settings.rules.push(rule),
+    }
+}
+
+/// Removes a time of an entry, `i` counts like in `time_labels`.
+pub fn remove_time(plan: &mut Plan, settings: &mut PlanSettings, i: usize) {
+    let times = plan.times.len();
+    let once = settings.once.len();
+    if i < times {
+        plan.times.remove(i);
+    } else if i < times + once {
+        settings.once.remove(i - times);
+    } else {
+        settings.rules.remove(i - times - once);
+    }
+}
+
+/// Which time of an entry launches at `at`, counting like in `time_labels`.
+pub fn time_index(plan: &Plan, settings: &PlanSettings, at: chrono::NaiveDateTime) -> Option<usize> {
+    let times = plan.times.len();
+    let once = settings.once.len();
+    plan.times.iter().position(|timeday| timeday.day == at.weekday() && timeday.time == at.time())
+        .or_else(|| settings.once.iter().position(|o| o.at == at).map(|i| times + i))
+        .or_else(|| settings.rules.iter()
+            .position(|rule| rule.time == at.time() && rule.repeat.falls_on(at.date()))
+            .map(|i| times + once + i))
+}
+
+/// Lists dates with a button to remove each and lets the user add more,
+/// returns whether the list changed.
+pub fn date_list(ui: &mut Ui, dates: &mut Vec<chrono::NaiveDate>, date: &mut DateSelection) -> bool {
+    let mut removed = None;
+    for (i, d) in dates.iter().enumerate() {
+        ui.horizontal( |ui| {
+            ui.add(egui::widgets::Label::new(d.format("%A %Y-%m-%d")));
+            if ui.button("remove").clicked() {
+                removed = Some(i);
+            }
+        });
+    }
+    if let Some(i) = removed {
+        dates.remove(i);
+        return true
+    }
+    date_selection_box(ui, date);
+    if ui.button("add date").clicked() {
+        if let Some(d) = date.date() {
+            if !dates.contains(&d) {
+                dates.push(d);
+                dates.sort();
+                return true
+            }
+        }
+    }
+    false
+}
+
+/// Lists what an entry does after opening its link with a button to remove each
+/// and lets the user add more, returns why the action couldn't be added, if it couldn't.
+pub fn action_list(ui: &mut Ui, actions: &mut Vec<Action>, selection: &mut ActionSelection) -> Result<(), String> {
+    let mut edited = None;
+    let mut removed = None;
+    for (i, action) in actions.iter().enumerate() {
+        ui.horizontal( |ui| {
+            let what = match action.kind {
+                ActionKind::Link { .. } => "open",
+                ActionKind::Command(_) => "run",
+            };
+            ui.add(egui::widgets::Label::new(format!("{} s later, {} {}", action.delay_secs, what, action.label())));
+            if selection.editing == Some(i) {
+                ui.add(egui::widgets::Label::new("(changing)"));
+            } else if ui.button("change").clicked() {
+                edited = Some(i);
+            }
+            if ui.button("remove").clicked() {
+                removed = Some(i);
+            }
+        });
+    }
+    if let Some(i) = edited {
+        *selection = ActionSelection::edit(i, &actions[i]);
+    }
+    if let Some(i) = removed {
+        actions.remove(i);
+        selection.editing = match selection.editing {
+            Some(editing) if editing == i => None,
+            Some(editing) if editing > i => Some(editing - 1),
+            editing => editing,
+        };
+    }
+    ui.horizontal( |ui| {
+        ui.radio_value(&mut selection.is_command, false, "link");
+        ui.radio_value(&mut selection.is_command, true, "command");
+    });
+    match selection.is_command {
+        false => {
+            ui.add(egui::widgets::TextEdit::singleline(&mut selection.url).hint_text("link"));
+        },
+        true => {
+            ui.add(egui::widgets::TextEdit::singleline(&mut selection.program).hint_text("program, like obs"));
+            ui.add(egui::widgets::TextEdit::singleline(&mut selection.args).hint_text("arguments, like --startrecording"));
+            ui.add(egui::widgets::TextEdit::singleline(&mut selection.dir).hint_text("working directory"));
+            ui.add(egui::widgets::TextEdit::singleline(&mut selection.env).hint_text("environment, like KEY=value"));
+            ui.horizontal( |ui| {
+                ui.add(egui::widgets::Label::new("killed after"));
+                ui.add(egui::widgets::DragValue::new(&mut selection.timeout_secs).clamp_range(1..=3600));
+                ui.add(egui::widgets::Label::new("seconds"));
+            });
+        },
+    }
+    ui.horizontal( |ui| {
+        ui.add(egui::widgets::DragValue::new(&mut selection.delay_secs).clamp_range(0..=3600));
+        ui.add(egui::widgets::Label::new("seconds after the one before"));
+    });
+    match selection.editing {
+        Some(i) => {
+            let mut saved = Ok(());
+            ui.horizontal( |ui| {
+                if ui.button("save action").clicked() {
+                    saved = selection.pick().map(|action| actions[i] = action);
+                    if saved.is_ok() {
+                        *selection = ActionSelection::default();
+                    }
+                }
+                if ui.button("cancel").clicked() {
+                    *selection = ActionSelection::default();
+                }
+            });
+            saved?;
+        },
+        None => if ui.button("add action").clicked() {
+            actions.push(selection.pick()?);
+            *selection = ActionSelection::default();
+        },
+    }
+    Ok(())
+}
+
+/// Lets the user pick what the links of an entry are opened with.
+pub fn launcher_selection_box(ui: &mut Ui, selection: &mut LauncherSelection) {
+    let launchers = ["default browser", "another browser", "a command"];
+    egui::containers::ComboBox::from_label("open with").show_index(ui, &mut selection.selected, launchers.len(), |i| {
+        String::from(launchers[i])
+    });
+    if selection.selected == 1 {
+        ui.add(egui::widgets::TextEdit::singleline(&mut selection.program).hint_text("browser, like firefox"));
+        ui.add(egui::widgets::TextEdit::singleline(&mut selection.args).hint_text("arguments, like -P \"work profile\""));
+    } else if selection.selected == 2 {
+        ui.add(egui::widgets::TextEdit::singleline(&mut selection.template).hint_text("command, like chromium --app={link}"));
+    }
+}
+
 pub fn usize_to_day(i: usize) -> chrono::Weekday {
     match i {
         0 => chrono::Weekday::Mon,
@@ -29,4 +259,49 @@ pub fn usize_to_day(i: usize) -> chrono::Weekday {
         6 => chrono::Weekday::Sun,
         _ => unreachable!(),
     }
-} 
\ No newline at end of file
+}
+
+pub fn catch_up_selection_box(ui: &mut Ui, selected: &mut usize, grace_minutes: &mut i64) {
+    let policies = ["launch late", "ask", "skip"];
+    egui::containers::ComboBox::from_label("if missed").show_index(ui, selected, policies.len(), |i| {
+        String::from(policies[i])
+    });
+    if *selected == 0 {
+        ui.horizontal( |ui| {
+            ui.add(egui::widgets::DragValue::new(grace_minutes).clamp_range(1..=1440));
+            ui.add(egui::widgets::Label::new("minutes late at most").wrap(true));
+        });
+    }
+}
+
+pub fn usize_to_catch_up(i: usize, grace_minutes: i64) -> CatchUp {
+    match i {
+        0 => CatchUp::Launch { grace_minutes },
+        1 => CatchUp::Ask,
+        2 => CatchUp::Skip,
+        _ => unreachable!(),
+    }
+}
+
+pub fn catch_up_to_usize(catch_up: CatchUp) -> (usize, i64) {
+    match catch_up {
+        CatchUp::Launch { grace_minutes } => (0, grace_minutes),
+        CatchUp::Ask => (1, 15),
+        CatchUp::Skip => (2, 15),
+    }
+}
+
+/// How long until something happens, as in "in 14 min".
+pub fn countdown(until: chrono::Duration) -> String {
+    let minutes = (until.num_seconds() + 59) / 60;
+    if minutes <= 0 {
+        String::from("now")
+    } else if minutes < 60 {
+        format!("in {} min", minutes)
+    } else if minutes < 24 * 60 {
+        format!("in {} h {} min", minutes / 60, minutes % 60)
+    } else {
+        let days = minutes / (24 * 60);
+        format!("in {} day{}", days, if days == 1 { "" } else { "s" })
+    }
+}
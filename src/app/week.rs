@@ -0,0 +1,91 @@
+use eframe::egui;
+use autolink_lib::chrono;
+use chrono::{Datelike, Timelike};
+use crate::scheduler::occurrence;
+use super::{App, utils};
+
+impl App {
+    /*
+     * the week section draws every launch of a week in a grid,
+     * days go from left to right and hours from top to bottom
+     * 
+     * the launches are worked out like the loop does, so dates,
+     * repeats, holidays and quiet times show up as they will happen
+     * 
+     * only the hours from the earliest to the latest launch are drawn,
+     * clicking a launch opens its time in the edit section
+    */
+    pub(super) fn week_section(&mut self, ui: &mut egui::Ui) {
+        let plans = self.plans.lock().unwrap().clone();
+        let today = chrono::Local::today().naive_local();
+        let monday = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64)
+            + chrono::Duration::weeks(self.state.week.offset);
+        ui.horizontal(|ui| {
+            if ui.small_button("previous week").clicked() {
+                self.state.week.offset -= 1;
+            }
+            if ui.small_button("this week").clicked() {
+                self.state.week.offset = 0;
+            }
+            if ui.small_button("next week").clicked() {
+                self.state.week.offset += 1;
+            }
+            ui.add(egui::widgets::Label::new(format!("week of {}", monday.format("%a %Y-%m-%d"))));
+        });
+        ui.add(egui::widgets::Separator::default().horizontal());
+
+        let after = monday.and_hms(0, 0, 0) - chrono::Duration::seconds(1);
+        let until = (monday + chrono::Duration::days(6)).and_hms(23, 59, 59);
+        let launches: Vec<(usize, chrono::NaiveDateTime)> = plans.iter().enumerate()
+            .flat_map(|(i, plan)| occurrence::between(plan, &self.settings, after, until).into_iter().map(move |at| (i, at)))
+            .collect();
+        let hours = launches.iter().map(|(_, at)| at.hour());
+        let (first, last) = match (hours.clone().min(), hours.max()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => {
+                ui.add(egui::widgets::Label::new("nothing is launched in this week").wrap(true));
+                return
+            },
+        };
+
+        let mut clicked = None;
+        egui::containers::ScrollArea::auto_sized().show(ui, |ui| {
+            egui::containers::Grid::new("week").striped(true).show(ui, |ui| {
+                ui.add(egui::widgets::Label::new(""));
+                for day in 0..7 {
+                    let date = monday + chrono::Duration::days(day as i64);
+                    ui.add(egui::widgets::Label::new(format!("{} {}", utils::usize_to_day(day), date.format("%d"))));
+                }
+                ui.end_row();
+
+                for hour in first..=last {
+                    ui.add(egui::widgets::Label::new(format!("{:02}:00", hour)));
+                    for day in 0..7 {
+                        let date = monday + chrono::Duration::days(day as i64);
+                        ui.vertical( |ui| {
+                            let mut these: Vec<&(usize, chrono::NaiveDateTime)> = launches.iter()
+                                .filter(|(_, at)| at.date() == date && at.hour() == hour)
+                                .collect();
+                            these.sort_by_key(|(_, at)| *at);
+                            for (i, at) in these {
+                                if ui.button(format!("{} {}", at.format("%H:%M"), plans[*i].name)).clicked() {
+                                    clicked = Some((*i, *at));
+                                }
+                            }
+                        });
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+
+        if let Some((selection, at)) = clicked {
+            let settings = self.settings.plan(&plans[selection].name);
+            let selected_time = utils::time_index(&plans[selection], &settings, at).unwrap_or(0);
+            self.state.edit_time(selection, selected_time);
+            if let Some(label) = utils::time_labels(&plans[selection], &settings).get(selected_time) {
+                self.state.edit.output = format!("selected {}, check \"remove time?\" to remove it", label);
+            }
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use autolink_lib::{Plan, TimeDay};
+use autolink_lib::chrono;
+use chrono::{NaiveTime, Weekday};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use std::path::PathBuf;
+use crate::history::{self, Record, Trigger};
+use crate::ipc::{self, Request};
+use crate::scheduler::occurrence;
+use crate::settings::{ActionKind, Launcher, Settings};
+use crate::{exchange, instance, launch, profiles};
+use crate::store::{self, find};
+
+/*
+ * the command line works on the same ~/.autolink as the app,
+ * an entry can be given by its name or by its index in `list`
+ * 
+ * when the app or the daemon is running, changes go through its socket instead,
+ * so that it doesn't write its own plans over them later
+ * 
+ * --profile picks the profile to work on, otherwise it is the one the loop runs,
+ * the app picks up changes to any other profile from the file
+*/
+pub fn app() -> App<'static, 'static> {
+    let entry = || Arg::with_name("entry").required(true).help("name or index of the entry");
+    let day = || Arg::with_name("day").required(true).help("day of the week, like mon or monday");
+    let time = || Arg::with_name("time").required(true).help("time of the day, like 09:30");
+    App::new("autolink")
+        .about("a simple app to open links at the right time")
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(Arg::with_name("headless").long("headless").alias("daemon").help("runs the loop without a window"))
+        .arg(Arg::with_name("profile").long("profile").short("p").takes_value(true).global(true)
+            .help("the profile to work on, or for the loop to run, instead of the one it runs"))
+        .subcommand(SubCommand::with_name("profiles").about("lists the profiles, marking the one the loop runs"))
+        .subcommand(SubCommand::with_name("list").about("lists every entry with its times"))
+        .subcommand(SubCommand::with_name("add").about("adds an entry")
+            .arg(Arg::with_name("name").required(true))
+            .arg(Arg::with_name("link").required(true))
+            .arg(Arg::with_name("at").long("at").takes_value(true).multiple(true).number_of_values(2)
+                .value_names(&["day", "time"]).help("adds a weekly time, can be given more than once")))
+        .subcommand(SubCommand::with_name("edit").about("changes the name or the link of an entry")
+            .arg(entry())
+            .arg(Arg::with_name("name").long("name").takes_value(true))
+            .arg(Arg::with_name("link").long("link").takes_value(true)))
+        .subcommand(SubCommand::with_name("remove").about("removes an entry")
+            .arg(entry()))
+        .subcommand(SubCommand::with_name("add-time").about("adds a weekly time to an entry")
+            .arg(entry()).arg(day()).arg(time()))
+        .subcommand(SubCommand::with_name("remove-time").about("removes a weekly time from an entry")
+            .arg(entry()).arg(day()).arg(time()))
+        .subcommand(SubCommand::with_name("launch").about("opens the link of an entry right away")
+            .arg(entry()))
+        .subcommand(SubCommand::with_name("import").about("adds the entries in a .ics or a .csv file")
+            .arg(Arg::with_name("file").required(true)))
+        .subcommand(SubCommand::with_name("export").about("writes every entry to a .ics or a .csv file")
+            .arg(Arg::with_name("file").required(true)))
+        .subcommand(SubCommand::with_name("start").about("starts the loop of the running app or daemon"))
+        .subcommand(SubCommand::with_name("stop").about("stops the loop of the running app or daemon"))
+        .subcommand(SubCommand::with_name("next").about("lists the next launches")
+            .arg(Arg::with_name("count").short("n").takes_value(true).default_value("5")))
+}
+
+pub fn run(command: &str, args: &ArgMatches) -> Result<(), String> {
+    let running = profiles::running();
+    if command == "profiles" {
+        for name in profiles::list() {
+            println!("{}{}", name, if name == running { " (running)" } else { "" });
+        }
+        return Ok(())
+    }
+    let profile = args.value_of("profile").map(String::from).unwrap_or(running.clone());
+    if !profiles::exists(&profile) && command != "add" && command != "import" {
+        return Err(format!("there is no profile called {}", profile))
+    }
+    let is_running = profile == running;
+    let mut settings = Settings::load(Settings::path(&profile));
+    let (mut plans, remote) = match ipc::send(&Request::List) {
+        Some(Ok(response)) if is_running => (response.plans, true),
+        Some(Err(e)) if is_running => return Err(format!("couldn't talk to the running app: {}", e)),
+        _ => (store::load(&profile)?, false),
+    };
+    let request = match command {
+        "list" => {
+            let today = chrono::Local::today().naive_local();
+            for (i, plan) in plans.iter().enumerate() {
+                let plan_settings = settings.plan(&plan.name);
+                let state = match plan_settings.paused_until.filter(|until| today < *until) {
+                    _ if !plan_settings.enabled => String::from(" disabled"),
+                    Some(until) => format!(" paused until {}", until),
+                    None => String::new(),
+                };
+                println!("{}: {} ({}){}", i, plan.name, plan.link, state);
+                match &plan_settings.launcher {
+                    Launcher::System => (),
+                    Launcher::Browser { program, args } => println!("    opened with {} {}", program, launch::join(args)),
+                    Launcher::Command { template } => println!("    opened with {}", template),
+                }
+                for action in &plan_settings.actions {
+                    let what = match action.kind {
+                        ActionKind::Link { .. } => "opens",
+                        ActionKind::Command(_) => "runs",
+                    };
+                    println!("    then {} {} after {}s", what, action.label(), action.delay_secs);
+                }
+                for TimeDay { day, time } in &plan.times {
+                    println!("    {} {}", day, time.format("%H:%M"));
+                }
+                for once in &plan_settings.once {
+                    println!("    {}", once.label());
+                }
+                for rule in &plan_settings.rules {
+                    println!("    {}", rule.label());
+                }
+                match (plan_settings.active_from, plan_settings.active_until) {
+                    (None, None) => (),
+                    (Some(from), None) => println!("    from {}", from),
+                    (None, Some(until)) => println!("    until {}", until),
+                    (Some(from), Some(until)) => println!("    from {} until {}", from, until),
+                }
+                if !plan_settings.skipped.is_empty() {
+                    let skipped: Vec<String> = plan_settings.skipped.iter().map(|date| date.to_string()).collect();
+                    println!("    skips {}", skipped.join(", "));
+                }
+            }
+            return Ok(())
+        },
+        "next" => {
+            let n = args.value_of("count").unwrap().parse().map_err(|_| String::from("the count must be a number"))?;
+            let now = chrono::Local::now().naive_local();
+            for (index, at) in occurrence::upcoming(&plans, &settings, now, n) {
+                let plan = &plans[index];
+                println!("{} - {} ({})", at.format("%A %Y-%m-%d %H:%M"), plan.name, plan.link);
+            }
+            return Ok(())
+        },
+        "export" => {
+            let path = PathBuf::from(args.value_of("file").unwrap());
+            for warning in exchange::write(&path, &plans, &settings)? {
+                eprintln!("{}", warning);
+            }
+            println!("wrote {} entries to {}", plans.len(), path.display());
+            return Ok(())
+        },
+        // the running app would write its own settings over those of the new entries
+        "import" if remote => return Err(String::from("import the file in the app, or close it first")),
+        "import" => {
+            let imported = exchange::read(&PathBuf::from(args.value_of("file").unwrap()))?;
+            for warning in &imported.warnings {
+                eprintln!("{}", warning);
+            }
+            for (plan, _) in &imported.entries {
+                println!("{} ({})", plan.name, plan.link);
+            }
+            exchange::add(&mut plans, &mut settings, imported.entries);
+            return save(&profile, plans, &settings, is_running)
+        },
+        "start" | "stop" if !is_running => return Err(format!("the loop runs the profile {}", running)),
+        "start" | "stop" if !remote => return Err(String::from("neither the app nor the daemon is running")),
+        "start" => return send(Request::Start),
+        "stop" => return send(Request::Stop),
+        "launch" if remote => return send(Request::Launch { entry: args.value_of("entry").unwrap().to_string() }),
+        "launch" => {
+            let plan = &plans[find(&plans, args.value_of("entry").unwrap())?];
+            let mut failed = Vec::new();
+            launch::open_all(plan, &settings.plan(&plan.name), |action, outcome| {
+                if let Some(output) = &outcome.output {
+                    println!("{}", output);
+                }
+                let record = Record {
+                    at: chrono::Local::now().naive_local(),
+                    name: plan.name.clone(),
+                    link: action.label(),
+                    trigger: Trigger::Manual,
+                    error: outcome.error.clone(),
+                    status: outcome.status,
+                    output: outcome.output,
+                };
+                let _ = history::append(&history::path(), &record);
+                if let Some(e) = record.error {
+                    failed.push(format!("couldn't launch {} ({}): {}", plan.name, record.link, e));
+                }
+            });
+            return match failed.is_empty() {
+                true => Ok(()),
+                false => Err(failed.join("\n")),
+            }
+        },
+        "add" => {
+            let mut times = Vec::new();
+            if let Some(values) = args.values_of("at") {
+                let values: Vec<&str> = values.collect();
+                for pair in values.chunks(2) {
+                    times.push(parse_time(pair[0], pair[1])?);
+                }
+            }
+            store::check_name(&plans, args.value_of("name").unwrap(), None)?;
+            let plan = Plan {
+                name: args.value_of("name").unwrap().to_string(),
+                link: args.value_of("link").unwrap().to_string(),
+                times,
+            };
+            plans.push(plan.clone());
+            Request::Add { plan, actions: None }
+        },
+        "edit" => {
+            let i = find(&plans, args.value_of("entry").unwrap())?;
+            let old = plans[i].clone();
+            if let Some(name) = args.value_of("name") {
+                store::check_name(&plans, name, Some(i))?;
+                settings.rename(&plans[i].name, name);
+                plans[i].name = name.to_string();
+            }
+            if let Some(link) = args.value_of("link") {
+                plans[i].link = link.to_string();
+            }
+            Request::Edit { old, plan: plans[i].clone(), actions: None }
+        },
+        "remove" => {
+            let i = find(&plans, args.value_of("entry").unwrap())?;
+            let plan = plans.remove(i);
+            settings.plans.remove(&plan.name);
+            Request::Remove { old: plan }
+        },
+        "add-time" => {
+            let i = find(&plans, args.value_of("entry").unwrap())?;
+            let timeday = parse_time(args.value_of("day").unwrap(), args.value_of("time").unwrap())?;
+            let old = plans[i].clone();
+            plans[i].times.push(timeday);
+            Request::Edit { old, plan: plans[i].clone(), actions: None }
+        },
+        "remove-time" => {
+            let i = find(&plans, args.value_of("entry").unwrap())?;
+            let timeday = parse_time(args.value_of("day").unwrap(), args.value_of("time").unwrap())?;
+            if !autolink_lib::check(&plans[i], &timeday) {
+                return Err(format!("{} has no time on {} at {}", plans[i].name, timeday.day, timeday.time))
+            }
+            let old = plans[i].clone();
+            plans[i].remove_matching_time(&timeday);
+            Request::Edit { old, plan: plans[i].clone(), actions: None }
+        },
+        _ => unreachable!(),
+    };
+    // the running app moves or drops the settings of the entry itself, and saves them when it closes
+    if remote {
+        return send(request)
+    }
+    save(&profile, plans, &settings, is_running)
+}
+
+/// Saves a profile, making it if it isn't there yet.
+fn save(profile: &str, plans: Vec<Plan>, settings: &Settings, is_running: bool) -> Result<(), String> {
+    // an app starting up in the meantime would read the file of the profile it runs before it is written
+    let _lock = match is_running {
+        true => Some(instance::acquire()?),
+        false => None,
+    };
+    if !profiles::exists(profile) {
+        profiles::create(profile)?;
+    }
+    store::save(profile, plans)?;
+    settings.save(Settings::path(profile))
+}
+
+/// Sends a request to the running app, which has already been found.
+fn send(request: Request) -> Result<(), String> {
+    match ipc::send(&request) {
+        Some(Ok(response)) if response.ok => Ok(()),
+        Some(Ok(response)) => Err(response.error.unwrap_or_default()),
+        Some(Err(e)) => Err(format!("couldn't talk to the running app: {}", e)),
+        None => Err(String::from("the running app has gone away")),
+    }
+}
+
+fn parse_time(day: &str, time: &str) -> Result<TimeDay, String> {
+    let day = day.parse::<Weekday>().map_err(|_| format!("{} isn't a day of the week", day))?;
+    let time = NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| format!("{} isn't a time like 09:30", time))?;
+    Ok(TimeDay::new(time, day))
+}
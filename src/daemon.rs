@@ -0,0 +1,214 @@
+use autolink_lib::chrono;
+use crossbeam_channel::{Receiver, select};
+use std::sync::{Arc, Mutex};
+use crate::scheduler::{Scheduler, SystemClock, SchedulerCommand, SchedulerEvent};
+use crate::settings::Settings;
+use crate::{history, ipc, store};
+use crate::store::FileEvent;
+
+/// What the daemon is asked to do from outside, by a signal.
+enum Signal {
+    Reload,
+    Stop,
+}
+
+/*
+ * the daemon runs the same loop as the app, without a window, on one profile,
+ * and logs whatever the loop does to stdout
+ * 
+ * SIGHUP reads the plans and the settings from disk again,
+ * a pause set in the app before it was closed carries on here
+ * SIGTERM and SIGINT shut it down after saving where the loop was
+ * 
+ * plans changed through the socket are saved as soon as the loop has them,
+ * and changes made to ~/.autolink or its settings by something else are picked up without a SIGHUP
+*/
+pub fn run(profile: &str) {
+    let plans = match store::load(profile) {
+        Ok(plans) => Arc::new(Mutex::new(plans)),
+        Err(e) => {
+            log(e);
+            std::process::exit(1);
+        },
+    };
+    let signals = match listen() {
+        Ok(signals) => signals,
+        Err(e) => {
+            log(format!("couldn't listen for signals: {}", e));
+            std::process::exit(1);
+        },
+    };
+    let mut settings = Settings::load(Settings::path(profile));
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    let (event_sender, events) = crossbeam_channel::unbounded();
+
+    let mut scheduler = Scheduler::new(SystemClock);
+    scheduler.record_to(history::path());
+    if let Some(checked_until) = settings.checked_until {
+        scheduler.resume_from(checked_until);
+    }
+    std::thread::spawn(move || scheduler.run(receiver, event_sender));
+
+    let (writer, file_events) = store::Writer::spawn(profile, plans.clone(), Some(sender.clone()));
+    log(format!("loaded {} entries from {}", plans.lock().unwrap().len(), store::path(profile).display()));
+    let _ = sender.send(SchedulerCommand::Reload(plans.lock().unwrap().clone(), settings.clone()));
+    if let Some(until) = settings.paused_until.filter(|until| *until > chrono::Local::now().naive_local()) {
+        let _ = sender.send(SchedulerCommand::PauseUntil(until));
+    }
+    let _ = sender.send(SchedulerCommand::Start);
+    if let Err(e) = ipc::serve(Arc::new(Mutex::new(plans.clone())), sender.clone()) {
+        log(format!("couldn't listen on {}: {}", ipc::path().display(), e));
+    }
+
+    loop {
+        select! {
+            recv(signals) -> signal => match signal {
+                Ok(Signal::Reload) => {
+                    settings = Settings::load(Settings::path(profile));
+                    let mut plans = plans.lock().unwrap();
+                    match store::load(profile) {
+                        Ok(loaded) => *plans = loaded,
+                        Err(e) => {
+                            log(format!("{}, kept the entries as they were", e));
+                            continue
+                        },
+                    }
+                    log(format!("reloaded {} entries", plans.len()));
+                    let _ = sender.send(SchedulerCommand::Reload(plans.clone(), settings.clone()));
+                },
+                Ok(Signal::Stop) | Err(_) => {
+                    log(String::from("shutting down"));
+                    let _ = sender.send(SchedulerCommand::Shutdown);
+                },
+            },
+            recv(file_events) -> event => match event {
+                Ok(FileEvent::Reloaded(entries)) => log(format!("{} changed, reloaded {} entries", store::path(profile).display(), entries)),
+                Ok(FileEvent::Conflicted(names)) => log(format!(
+                    "{} changed, kept the changes made through the socket to {}", store::path(profile).display(), names.join(", ")
+                )),
+                Ok(FileEvent::Failed(e)) => log(e),
+                Ok(FileEvent::SettingsChanged { known, disk }) => {
+                    let (merged, conflicts) = store::merge_settings(&known, &settings, &disk);
+                    if store::same_settings(&merged, &settings) {
+                        continue
+                    }
+                    settings = merged;
+                    if !store::same_settings(&settings, &disk) {
+                        settings.save(Settings::path(profile)).unwrap_or_else(log);
+                    }
+                    let _ = sender.send(SchedulerCommand::Reload(plans.lock().unwrap().clone(), settings.clone()));
+                    log(match conflicts.is_empty() {
+                        true => format!("{} changed, took the settings in", Settings::path(profile).display()),
+                        false => format!(
+                            "{} changed, kept the settings of {} as they were here", Settings::path(profile).display(), conflicts.join(", ")
+                        ),
+                    });
+                },
+                Err(_) => (),
+            },
+            recv(events) -> event => match event {
+                Ok(SchedulerEvent::ShutDown { checked_until }) => {
+                    ipc::close();
+                    writer.finish();
+                    for event in file_events.try_iter() {
+                        if let FileEvent::Failed(e) = event {
+                            log(e);
+                        }
+                    }
+                    settings.checked_until = checked_until;
+                    settings.save(Settings::path(profile)).unwrap_or_else(log);
+                    return
+                },
+                Ok(SchedulerEvent::OnceDone { name, at }) => {
+                    if let Some(settings) = settings.plans.get_mut(&name) {
+                        for once in settings.once.iter_mut().filter(|once| once.at == at) {
+                            once.done = true;
+                        }
+                    }
+                    settings.save(Settings::path(profile)).unwrap_or_else(log);
+                },
+                Ok(SchedulerEvent::Renamed { from, to }) => {
+                    settings.rename(&from, &to);
+                    settings.save(Settings::path(profile)).unwrap_or_else(log);
+                    log(describe(SchedulerEvent::Renamed { from, to }));
+                },
+                Ok(SchedulerEvent::ActionsSet { name, actions }) => {
+                    settings.plans.entry(name.clone()).or_default().actions = actions.clone();
+                    settings.save(Settings::path(profile)).unwrap_or_else(log);
+                    log(describe(SchedulerEvent::ActionsSet { name, actions }));
+                },
+                Ok(SchedulerEvent::Forgotten(name)) => {
+                    settings.plans.remove(&name);
+                    settings.save(Settings::path(profile)).unwrap_or_else(log);
+                    log(describe(SchedulerEvent::Forgotten(name)));
+                },
+                Ok(SchedulerEvent::Paused(until)) => {
+                    settings.paused_until = Some(until);
+                    settings.save(Settings::path(profile)).unwrap_or_else(log);
+                    log(describe(SchedulerEvent::Paused(until)));
+                },
+                Ok(SchedulerEvent::Resumed) => {
+                    settings.paused_until = None;
+                    settings.save(Settings::path(profile)).unwrap_or_else(log);
+                    log(describe(SchedulerEvent::Resumed));
+                },
+                Ok(SchedulerEvent::Reloaded(entries)) => {
+                    writer.write(plans.lock().unwrap().clone());
+                    log(describe(SchedulerEvent::Reloaded(entries)));
+                },
+                Ok(event) => log(describe(event)),
+                Err(_) => return,
+            },
+        }
+    }
+}
+
+fn log(message: String) {
+    println!("{} {}", chrono::Local::now().format("%Y-%m-%d %H:%M:%S"), message);
+}
+
+fn describe(event: SchedulerEvent) -> String {
+    match event {
+        SchedulerEvent::Started => String::from("the loop has started"),
+        SchedulerEvent::Stopped => String::from("the loop has stopped"),
+        SchedulerEvent::Reloaded(entries) => format!("the loop has {} entries", entries),
+        SchedulerEvent::Paused(until) => format!("paused until {}", until),
+        SchedulerEvent::Resumed => String::from("resumed"),
+        SchedulerEvent::Launched { plan, link, at, error: None } => format!("launched {} ({}) at {}", plan.name, link, at),
+        SchedulerEvent::Launched { plan, link, error: Some(e), .. } => format!("couldn't launch {} ({}): {}", plan.name, link, e),
+        // there is nobody to ask, so it is only logged
+        SchedulerEvent::Missed { plan, at } => format!("missed {} which was due at {}", plan.name, at),
+        SchedulerEvent::Gone { name } => format!("didn't launch {}, it was changed or removed", name),
+        SchedulerEvent::Skipped { plan, at, quiet: false } => format!("skipped {} which was due at {}", plan.name, at),
+        SchedulerEvent::Skipped { plan, at, quiet: true } => format!("skipped {} which was due at {}, it is quiet time", plan.name, at),
+        SchedulerEvent::HistoryFailed(e) => e,
+        SchedulerEvent::Renamed { from, to } => format!("{} was renamed to {}", from, to),
+        SchedulerEvent::Forgotten(name) => format!("{} was removed", name),
+        SchedulerEvent::ActionsSet { name, .. } => format!("{} has new actions", name),
+        SchedulerEvent::OnceDone { name, at } => format!("{} at {} is done", name, at),
+        SchedulerEvent::ClockJumped { from, to } => format!("the clock jumped from {} to {}", from, to),
+        SchedulerEvent::ShutDown { .. } => String::from("the loop has shut down"),
+    }
+}
+
+#[cfg(unix)]
+fn listen() -> std::io::Result<Receiver<Signal>> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    let mut signals = signal_hook::iterator::Signals::new([SIGHUP, SIGINT, SIGTERM])?;
+    std::thread::spawn(move || {
+        for signal in signals.forever() {
+            let _ = sender.send(match signal {
+                SIGHUP => Signal::Reload,
+                _ => Signal::Stop,
+            });
+        }
+    });
+    Ok(receiver)
+}
+
+/// Without signals the daemon runs until it is killed.
+#[cfg(not(unix))]
+fn listen() -> std::io::Result<Receiver<Signal>> {
+    Ok(crossbeam_channel::never())
+}
@@ -0,0 +1,112 @@
+use autolink_lib::Plan;
+use std::path::Path;
+use crate::settings::{PlanSettings, Settings};
+
+pub mod csv;
+pub mod ics;
+
+/*
+ * entries can be read from and written to other formats,
+ * so they can be shared with calendars and spreadsheets
+ * 
+ * the format is picked by the extension of the file
+ * 
+ * what is read is shown to the user first, so it is kept apart
+ * from the plans until it is added to them
+*/
+#[derive(Default)]
+pub struct Imported {
+    pub entries: Vec<(Plan, PlanSettings)>,
+    /// What couldn't be read, and why.
+    pub warnings: Vec<String>,
+}
+
+impl Imported {
+    /// Adds an entry, joining it with an entry of the same name read before.
+    fn push(&mut self, plan: Plan, settings: PlanSettings) {
+        match self.entries.iter_mut().find(|(entry, _)| entry.name == plan.name) {
+            Some((entry, entry_settings)) => {
+                entry_settings.active_from = match (entry_settings.active_from, settings.active_from) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    _ => None,
+                };
+                entry_settings.active_until = match (entry_settings.active_until, settings.active_until) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    _ => None,
+                };
+                join(entry, entry_settings, plan, settings);
+            },
+            None => self.entries.push((plan, settings)),
+        }
+    }
+}
+
+/// Reads a file in the format its extension names.
+pub fn read(path: &Path) -> Result<Imported, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+    match extension(path).as_str() {
+        "ics" => ics::import(&contents),
+        "csv" => csv::import(&contents),
+        _ => Err(String::from("only .ics and .csv files can be read")),
+    }
+    .map_err(|e| format!("couldn't read {}: {}", path.display(), e))
+}
+
+/// Writes every entry to a file in the format its extension names, gives what the file couldn't hold.
+pub fn write(path: &Path, plans: &[Plan], settings: &Settings) -> Result<Vec<String>, String> {
+    let contents = match extension(path).as_str() {
+        "ics" => ics::export(plans, settings),
+        "csv" => csv::export(plans)?,
+        _ => return Err(String::from("only .ics and .csv files can be written")),
+    };
+    std::fs::write(path, contents).map_err(|e| format!("couldn't write {}: {}", path.display(), e))?;
+    // neither format has room for what an entry does after opening its link
+    Ok(plans.iter()
+        .filter(|plan| !settings.plan(&plan.name).actions.is_empty())
+        .map(|plan| format!("{} opens other links or runs commands too, only its own link was written", plan.name))
+        .collect())
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+/// Adds what was read to the plans, an entry named like one there already only adds its times to it.
+pub fn add(plans: &mut Vec<Plan>, settings: &mut Settings, entries: Vec<(Plan, PlanSettings)>) {
+    for (plan, plan_settings) in entries {
+        match plans.iter_mut().find(|entry| entry.name == plan.name) {
+            Some(entry) => {
+                let entry_settings = settings.plans.entry(plan.name.clone()).or_default();
+                join(entry, entry_settings, plan, plan_settings);
+            },
+            None => {
+                settings.plans.insert(plan.name.clone(), plan_settings);
+                plans.push(plan);
+            },
+        }
+    }
+}
+
+/// Adds the times of one entry to another, leaving out those it has already.
+fn join(plan: &mut Plan, settings: &mut PlanSettings, other: Plan, other_settings: PlanSettings) {
+    for timeday in other.times {
+        if !autolink_lib::check(plan, &timeday) {
+            plan.times.push(timeday);
+        }
+    }
+    for once in other_settings.once {
+        if !settings.once.contains(&once) {
+            settings.once.push(once);
+        }
+    }
+    for rule in other_settings.rules {
+        if !settings.rules.contains(&rule) {
+            settings.rules.push(rule);
+        }
+    }
+    for date in other_settings.skipped {
+        if !settings.skipped.contains(&date) {
+            settings.skipped.push(date);
+        }
+    }
+}
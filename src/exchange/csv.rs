@@ -0,0 +1,159 @@
+use autolink_lib::{Plan, TimeDay};
+use autolink_lib::chrono::{NaiveTime, Weekday};
+use crate::settings::PlanSettings;
+use super::Imported;
+
+/// The columns, in the order they are written.
+const HEADER: [&str; 4] = ["name", "link", "weekday", "time"];
+
+/*
+ * spreadsheets, with a row for each weekly time of an entry:
+ * 
+ * name,link,weekday,time
+ * maths,https://example.com/maths,Mon,09:00
+ * maths,https://example.com/maths,Wed,10:30
+ * 
+ * the columns are found by their header, or else taken in this order,
+ * rows with the same name are joined into one entry, and a row without
+ * a weekday and a time adds an entry with no times
+ * 
+ * only weekly times are written, the rest of an entry's times
+ * don't fit into a row
+*/
+pub fn import(contents: &str) -> Result<Imported, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(contents.as_bytes());
+    let mut rows = Vec::new();
+    for row in reader.records() {
+        rows.push(row.map_err(|e| e.to_string())?);
+    }
+    let header: Vec<String> = match rows.first() {
+        Some(first) => first.iter().map(|cell| cell.to_lowercase()).collect(),
+        None => return Err(String::from("it is empty")),
+    };
+    let find = |names: &[&str]| header.iter().position(|cell| names.contains(&cell.as_str()));
+    let (columns, skip) = match (find(&["name"]), find(&["link", "url"])) {
+        (Some(name), Some(link)) => ([Some(name), Some(link), find(&["weekday", "day"]), find(&["time"])], 1),
+        _ => ([Some(0), Some(1), Some(2), Some(3)], 0),
+    };
+
+    let mut imported = Imported::default();
+    for (i, row) in rows.iter().enumerate().skip(skip) {
+        let cell = |column: usize| columns[column].and_then(|column| row.get(column)).unwrap_or("");
+        // spreadsheets often end with empty rows
+        if row.iter().all(|cell| cell.is_empty()) {
+            continue
+        }
+        match read_row(cell(0), cell(1), cell(2), cell(3)) {
+            Ok(plan) => {
+                let different = imported.entries.iter().any(|(entry, _)| entry.name == plan.name && entry.link != plan.link);
+                if different {
+                    imported.warnings.push(format!("row {}: {} has another link in a row before, that one was kept", i + 1, plan.name));
+                }
+                imported.push(plan, PlanSettings::default());
+            },
+            Err(e) => imported.warnings.push(format!("row {}: {}, it was left out", i + 1, e)),
+        }
+    }
+    Ok(imported)
+}
+
+fn read_row(name: &str, link: &str, weekday: &str, time: &str) -> Result<Plan, String> {
+    if name.is_empty() || link.is_empty() {
+        return Err(String::from("both name and link must be there"))
+    }
+    let mut plan = Plan { name: name.to_string(), link: link.to_string(), times: Vec::new() };
+    match (weekday, time) {
+        ("", "") => (),
+        ("", _) | (_, "") => return Err(String::from("the weekday and the time go together")),
+        (weekday, time) => {
+            let day = weekday.parse::<Weekday>().map_err(|_| format!("\"{}\" isn't a day of the week", weekday))?;
+            let time = NaiveTime::parse_from_str(time, "%H:%M")
+                .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M:%S"))
+                .map_err(|_| format!("\"{}\" isn't a time like 09:30", time))?;
+            plan.times.push(TimeDay::new(time, day));
+        },
+    }
+    Ok(plan)
+}
+
+pub fn export(plans: &[Plan]) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut write = |row: &[&str]| writer.write_record(row).map_err(|e| e.to_string());
+    write(&HEADER)?;
+    for plan in plans {
+        if plan.times.is_empty() {
+            write(&[&plan.name, &plan.link, "", ""])?;
+        }
+        for TimeDay { day, time } in &plan.times {
+            write(&[&plan.name, &plan.link, &day.to_string(), &time.format("%H:%M").to_string()])?;
+        }
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    Ok(String::from_utf8(bytes).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_of_an_entry_are_joined() {
+        let imported = import("name,link,weekday,time\nmaths,https://example.com/maths,Mon,09:00\nmaths,https://example.com/maths,Wed,10:30:00\n,,,\n").unwrap();
+        assert_eq!(imported.entries.len(), 1);
+        let (plan, _) = &imported.entries[0];
+        assert_eq!(plan.name, "maths");
+        assert!(autolink_lib::check(plan, &TimeDay::new(NaiveTime::from_hms(9, 0, 0), Weekday::Mon)));
+        assert!(autolink_lib::check(plan, &TimeDay::new(NaiveTime::from_hms(10, 30, 0), Weekday::Wed)));
+        assert!(imported.warnings.is_empty());
+    }
+
+    #[test]
+    fn columns_are_found_by_their_header() {
+        let imported = import("Time,Day,URL,Name\n09:00,tue,https://example.com/art,art\n").unwrap();
+        let (plan, _) = &imported.entries[0];
+        assert_eq!(plan.link, "https://example.com/art");
+        assert!(autolink_lib::check(plan, &TimeDay::new(NaiveTime::from_hms(9, 0, 0), Weekday::Tue)));
+        // without a header the columns are taken in order
+        let imported = import("art, https://example.com/art\n").unwrap();
+        assert_eq!(imported.entries[0].0.name, "art");
+        assert!(imported.entries[0].0.times.is_empty());
+    }
+
+    #[test]
+    fn rows_that_cant_be_read_are_left_out_with_a_warning() {
+        let imported = import(
+            "name,link,weekday,time\nmaths,,Mon,09:00\nart,https://example.com/art,Someday,09:00\nart,https://example.com/art,Mon,\nart,https://example.com/art,Thu,08:00\nart,https://example.com/other,Fri,12:00\n"
+        ).unwrap();
+        assert_eq!(imported.entries.len(), 1);
+        assert_eq!(imported.entries[0].0.link, "https://example.com/art");
+        assert_eq!(imported.warnings, vec![
+            "row 2: both name and link must be there, it was left out",
+            "row 3: \"Someday\" isn't a day of the week, it was left out",
+            "row 4: the weekday and the time go together, it was left out",
+            "row 6: art has another link in a row before, that one was kept",
+        ]);
+        assert_eq!(import("").err(), Some(String::from("it is empty")));
+    }
+
+    #[test]
+    fn exported_rows_import_the_same() {
+        let plans = vec![
+            Plan {
+                name: String::from("maths, year 2"),
+                link: String::from("https://example.com/maths"),
+                times: vec![TimeDay::new(NaiveTime::from_hms(9, 0, 0), Weekday::Mon)],
+            },
+            Plan { name: String::from("art"), link: String::from("https://example.com/art"), times: Vec::new() },
+        ];
+        let imported = import(&export(&plans).unwrap()).unwrap();
+        assert_eq!(imported.entries.len(), 2);
+        assert_eq!(imported.entries[0].0.name, "maths, year 2");
+        assert!(autolink_lib::check(&imported.entries[0].0, &plans[0].times[0]));
+        assert_eq!(imported.entries[1].0.name, "art");
+        assert!(imported.entries[1].0.times.is_empty());
+    }
+}
@@ -0,0 +1,580 @@
+use autolink_lib::{Plan, TimeDay};
+use autolink_lib::chrono;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Weekday};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use crate::settings::{Once, PlanSettings, Repeat, Rule, Settings};
+use super::Imported;
+
+/// Calendar events need an end, entries only have a start.
+const EVENT_MINUTES: i64 = 60;
+
+/// How far ahead an export looks for the first day an event falls on.
+const SEARCH_DAYS: i64 = 400;
+
+/// How far from its start the last of a number of repeats is looked for.
+const COUNT_DAYS: i64 = 100 * 366;
+
+/*
+ * iCalendar (RFC 5545) files, as calendar apps export and import them
+ * 
+ * an event becomes an entry named after its SUMMARY, launching its URL,
+ * or else the first link in its LOCATION or DESCRIPTION
+ * 
+ * weekly repeats become weekly times, every n weeks, daily and monthly
+ * repeats become repeats, events that don't repeat happen once,
+ * EXDATE becomes skipped days and UNTIL, or the last of COUNT repeats, the last day of the entry
+ * 
+ * UTC times and times in a time zone named by TZID are moved to the local time zone,
+ * times without either are read as local times
+ * 
+ * events are written in local time, with UNTIL in UTC, and a UID made from the entry
+ * and the time, so that writing the entries again updates the events in a calendar
+*/
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+/// Which days an event being written falls on, to find its first one.
+type FallsOn = Box<dyn Fn(NaiveDate) -> bool>;
+
+pub fn import(contents: &str) -> Result<Imported, String> {
+    let lines = unfold(contents);
+    if !lines.iter().any(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err(String::from("it isn't an iCalendar file"))
+    }
+    let now = chrono::Local::now().naive_local();
+    let mut imported = Imported::default();
+    let mut event: Option<Vec<Property>> = None;
+    // alarms and such inside an event have properties of their own
+    let mut nested: usize = 0;
+    let mut over = 0;
+    for line in &lines {
+        let upper = line.to_ascii_uppercase();
+        match event.as_mut() {
+            None if upper == "BEGIN:VEVENT" => event = Some(Vec::new()),
+            None => (),
+            Some(_) if upper == "END:VEVENT" && nested == 0 => {
+                let is_read = read_event(event.take().unwrap(), now, &mut imported);
+                if !is_read {
+                    over += 1;
+                }
+            },
+            Some(_) if upper.starts_with("BEGIN:") => nested += 1,
+            Some(_) if upper.starts_with("END:") => nested = nested.saturating_sub(1),
+            Some(properties) if nested == 0 => properties.extend(property(line)),
+            Some(_) => (),
+        }
+    }
+    match over {
+        0 => (),
+        1 => imported.warnings.push(String::from("left out an event which is over")),
+        over => imported.warnings.push(format!("left out {} events which are over", over)),
+    }
+    Ok(imported)
+}
+
+/// Adds an event to what was imported, gives false if it was left out for being over.
+fn read_event(properties: Vec<Property>, now: NaiveDateTime, imported: &mut Imported) -> bool {
+    let get = |name: &str| properties.iter().find(|property| property.name == name);
+    // a changed occurrence of another event, which is already there
+    if get("RECURRENCE-ID").is_some() {
+        return true
+    }
+    if get("STATUS").map(|status| status.value.eq_ignore_ascii_case("CANCELLED")).unwrap_or(false) {
+        return true
+    }
+    let name = match get("SUMMARY").map(|summary| unescape(&summary.value)) {
+        Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+        _ => {
+            imported.warnings.push(String::from("an event without a summary was left out"));
+            return true
+        },
+    };
+    let link = get("URL").map(|url| unescape(url.value.trim()))
+        .or_else(|| get("LOCATION").and_then(|location| find_link(&unescape(&location.value))))
+        .or_else(|| get("DESCRIPTION").and_then(|description| find_link(&unescape(&description.value))));
+    let link = match link {
+        Some(link) => link,
+        None => {
+            imported.warnings.push(format!("{} has no link, it was left out", name));
+            return true
+        },
+    };
+    let start = match get("DTSTART").map(|start| (date_time(start), zone(start))) {
+        Some((Some(start), _)) => start,
+        Some((None, Err(zone))) => {
+            imported.warnings.push(format!("{} is in the time zone {} which autolink doesn't know, it was left out", name, zone));
+            return true
+        },
+        Some((None, Ok(_))) => {
+            imported.warnings.push(format!("{} lasts all day, it was left out", name));
+            return true
+        },
+        None => {
+            imported.warnings.push(format!("{} has no start, it was left out", name));
+            return true
+        },
+    };
+    let time = NaiveTime::from_hms(start.hour(), start.minute(), 0);
+    let mut plan = Plan { name, link, times: Vec::new() };
+    let mut settings = PlanSettings::default();
+    match get("RRULE") {
+        None if start < now => return false,
+        None => settings.once.push(Once::new(start.date().and_time(time))),
+        Some(rrule) => {
+            // the days it repeats on are those of its own time zone, which may be a day off here
+            let shift = get("DTSTART").and_then(written).map(|written| (start.date() - written.date()).num_days()).unwrap_or(0);
+            if let Err(e) = recurrence(&rrule.value, start, shift, &mut plan, &mut settings) {
+                imported.warnings.push(format!("{} {}, it was left out", plan.name, e));
+                return true
+            }
+            if settings.active_until.map(|until| until < now.date()).unwrap_or(false) {
+                return false
+            }
+            settings.active_from = Some(start.date());
+            for exdate in properties.iter().filter(|property| property.name == "EXDATE") {
+                settings.skipped.extend(exdate.value.split(',').filter_map(|value| parse_date_time(value, exdate)).map(|at| at.date()));
+            }
+        },
+    }
+    imported.push(plan, settings);
+    true
+}
+
+/// Reads an RRULE into the times of an entry, `shift` is how many days later the start is here than where it was written.
+fn recurrence(rrule: &str, start: NaiveDateTime, shift: i64, plan: &mut Plan, settings: &mut PlanSettings) -> Result<(), String> {
+    let parts: HashMap<String, String> = rrule.split(';')
+        .filter_map(|part| {
+            let mut pair = part.splitn(2, '=');
+            Some((pair.next()?.to_ascii_uppercase(), pair.next()?.to_ascii_uppercase()))
+        })
+        .collect();
+    let freq = parts.get("FREQ").map(String::as_str).unwrap_or("");
+    let interval: u32 = parts.get("INTERVAL").and_then(|interval| interval.parse().ok()).unwrap_or(1);
+    let count = match parts.get("COUNT") {
+        Some(count) => Some(count.parse::<usize>().map_err(|_| format!("repeats {} times, which isn't a number", count))?),
+        None => None,
+    };
+    let mut days = Vec::new();
+    for day in parts.get("BYDAY").map(|days| days.split(',').collect::<Vec<_>>()).unwrap_or_default() {
+        let (nth, code) = day.split_at(day.len().saturating_sub(2));
+        let weekday = from_code(code).ok_or(format!("repeats on {} which isn't a day", day))?;
+        let nth = match nth {
+            "" => None,
+            nth => Some(nth.trim_start_matches('+').parse::<i32>().map_err(|_| format!("repeats on {} which isn't a day", day))?),
+        };
+        let weekday = match shift {
+            0 => weekday,
+            _ if nth.is_some() => return Err(String::from("repeats on a day of the month which is another day here")),
+            shift => (0..shift.rem_euclid(7)).fold(weekday, |day, _| day.succ()),
+        };
+        days.push((nth, weekday));
+    }
+    let time = NaiveTime::from_hms(start.hour(), start.minute(), 0);
+    match (freq, interval) {
+        ("DAILY", 1) if days.is_empty() => settings.rules.push(Rule { repeat: Repeat::Daily, time }),
+        ("DAILY", 1) | ("WEEKLY", 1) => {
+            if days.is_empty() {
+                days.push((None, start.weekday()));
+            }
+            plan.times.extend(days.iter().map(|&(_, day)| TimeDay::new(time, day)));
+        },
+        ("WEEKLY", weeks) => {
+            if days.is_empty() {
+                days.push((None, start.weekday()));
+            }
+            settings.rules.extend(days.iter().map(|&(_, day)| Rule {
+                repeat: Repeat::EveryNWeeks { weeks, day, anchor: start.date() },
+                time,
+            }));
+        },
+        ("MONTHLY", 1) if !days.is_empty() => for (nth, day) in days {
+            let nth = match nth {
+                Some(nth) if (1..=4).contains(&nth) => nth as u32,
+                Some(-1) => 5,
+                _ => return Err(String::from("repeats on a day of the month autolink can't do")),
+            };
+            settings.rules.push(Rule { repeat: Repeat::MonthlyNth { nth, day }, time });
+        },
+        _ => return Err(format!("repeats in a way autolink can't do ({})", rrule)),
+    }
+    if let Some(until) = parts.get("UNTIL") {
+        let date = match until.strip_suffix('Z') {
+            Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()
+                .map(|at| chrono::Utc.from_utc_datetime(&at).with_timezone(&chrono::Local).date().naive_local()),
+            None => NaiveDate::parse_from_str(until.get(..8).unwrap_or(""), "%Y%m%d").ok(),
+        };
+        settings.active_until = Some(date.ok_or(format!("repeats until {} which isn't a date", until))?);
+    }
+    if let Some(count) = count {
+        // the event's own times are all the entry has so far
+        let falls_on = |date: NaiveDate| {
+            plan.times.iter().any(|timeday| timeday.day == date.weekday())
+                || settings.rules.iter().any(|rule| rule.repeat.falls_on(date))
+        };
+        let last = (0..COUNT_DAYS)
+            .map(|days| start.date() + chrono::Duration::days(days))
+            .filter(|date| falls_on(*date))
+            .nth(count.max(1) - 1);
+        settings.active_until = Some(last.ok_or(format!("repeats {} times, which goes on too long", count))?);
+    }
+    Ok(())
+}
+
+/// Writes the entries as events, which repeat like the entries do.
+pub fn export(plans: &[Plan], settings: &Settings) -> String {
+    let now = chrono::Local::now().naive_local();
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//autolink//autolink-egui//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+    ];
+    for plan in plans {
+        let plan_settings = settings.plan(&plan.name);
+        let from = plan_settings.active_from.map(|from| from.max(now.date())).unwrap_or(now.date());
+        // the rule of each event, its time, and the days it falls on
+        let mut events: Vec<(String, NaiveTime, FallsOn)> = Vec::new();
+
+        let mut weekly: Vec<(NaiveTime, Vec<Weekday>)> = Vec::new();
+        for TimeDay { day, time } in &plan.times {
+            match weekly.iter_mut().find(|(at, _)| at == time) {
+                Some((_, days)) => days.push(*day),
+                None => weekly.push((*time, vec![*day])),
+            }
+        }
+        for (time, days) in weekly {
+            let rrule = format!("FREQ=WEEKLY;BYDAY={}", days.iter().map(|day| code(*day)).collect::<Vec<_>>().join(","));
+            events.push((rrule, time, Box::new(move |date: NaiveDate| days.contains(&date.weekday()))));
+        }
+        for rule in plan_settings.rules.iter().cloned() {
+            let rrule = match rule.repeat {
+                Repeat::Daily => String::from("FREQ=DAILY"),
+                Repeat::Weekdays => String::from("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR"),
+                Repeat::EveryNWeeks { weeks, day, .. } => format!("FREQ=WEEKLY;INTERVAL={};BYDAY={}", weeks, code(day)),
+                Repeat::MonthlyNth { nth: 5, day } => format!("FREQ=MONTHLY;BYDAY=-1{}", code(day)),
+                Repeat::MonthlyNth { nth, day } => format!("FREQ=MONTHLY;BYDAY={}{}", nth, code(day)),
+            };
+            events.push((rrule, rule.time, Box::new(move |date| rule.repeat.falls_on(date))));
+        }
+
+        let skipped: Vec<NaiveDate> = plan_settings.skipped.iter().chain(&settings.holidays).cloned().collect();
+        for (rrule, time, falls_on) in events {
+            let first = (0..SEARCH_DAYS).map(|days| from + chrono::Duration::days(days)).find(|date| falls_on(*date));
+            let first = match first {
+                Some(first) if plan_settings.active_until.map(|until| first <= until).unwrap_or(true) => first,
+                _ => continue,
+            };
+            let uid = uid(&plan.name, &format!("{} {}", rrule, time));
+            let rrule = match plan_settings.active_until {
+                Some(until) => format!("{};UNTIL={}", rrule, format_utc(until.and_hms(23, 59, 59))),
+                None => rrule,
+            };
+            let exdates: Vec<String> = skipped.iter()
+                .filter(|date| **date >= first && falls_on(**date))
+                .map(|date| format_date_time(date.and_time(time)))
+                .collect();
+            let mut event = vec![format!("RRULE:{}", rrule)];
+            if !exdates.is_empty() {
+                event.push(format!("EXDATE:{}", exdates.join(",")));
+            }
+            lines.extend(write_event(plan, uid, &stamp, first.and_time(time), event));
+        }
+        for once in plan_settings.once.iter().filter(|once| !once.done) {
+            lines.extend(write_event(plan, uid(&plan.name, &format_date_time(once.at)), &stamp, once.at, Vec::new()));
+        }
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    lines.iter().map(|line| fold(line)).collect::<Vec<_>>().join("\r\n") + "\r\n"
+}
+
+fn write_event(plan: &Plan, uid: String, stamp: &str, start: NaiveDateTime, rest: Vec<String>) -> Vec<String> {
+    let mut lines = vec![
+        String::from("BEGIN:VEVENT"),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", stamp),
+        format!("DTSTART:{}", format_date_time(start)),
+        format!("DURATION:PT{}M", EVENT_MINUTES),
+        format!("SUMMARY:{}", escape(&plan.name)),
+        format!("URL:{}", uri(&plan.link)),
+        format!("DESCRIPTION:{}", escape(&plan.link)),
+    ];
+    lines.extend(rest);
+    lines.push(String::from("END:VEVENT"));
+    lines
+}
+
+/// Joins lines that were folded, a line starting with a space carries on the one before.
+fn unfold(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        match (line.chars().next(), lines.last_mut()) {
+            (Some(' '), Some(last)) | (Some('\t'), Some(last)) => last.push_str(&line[1..]),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Lines are at most 75 bytes long, the rest carries on in lines starting with a space.
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// Reads a line like `DTSTART;TZID=Europe/Berlin:20210906T083000`.
+fn property(line: &str) -> Option<Property> {
+    let mut quoted = false;
+    let colon = line.char_indices().find(|&(_, c)| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?.0;
+    let mut head = line[..colon].split(';');
+    let name = head.next()?.to_ascii_uppercase();
+    let params = head
+        .filter_map(|param| {
+            let mut pair = param.splitn(2, '=');
+            Some((pair.next()?.to_ascii_uppercase(), pair.next()?.trim_matches('"').to_string()))
+        })
+        .collect();
+    Some(Property { name, params, value: line[colon + 1..].to_string() })
+}
+
+/// Reads the time a property has in the local time zone, None for a whole day or an unknown time zone.
+fn date_time(property: &Property) -> Option<NaiveDateTime> {
+    parse_date_time(&property.value, property)
+}
+
+fn parse_date_time(value: &str, property: &Property) -> Option<NaiveDateTime> {
+    let is_date = property.params.iter().any(|(name, value)| name == "VALUE" && value.eq_ignore_ascii_case("DATE"));
+    let value = value.trim();
+    if is_date || value.len() == 8 {
+        return None
+    }
+    match (value.strip_suffix('Z'), zone(property).ok()?) {
+        (Some(utc), _) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()
+            .map(|at| chrono::Utc.from_utc_datetime(&at).with_timezone(&chrono::Local).naive_local()),
+        (None, Some(zone)) => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+            .and_then(|at| zone.from_local_datetime(&at).earliest())
+            .map(|at| at.with_timezone(&chrono::Local).naive_local()),
+        (None, None) => NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok(),
+    }
+}
+
+/// The time of a property as it is written, whatever its time zone.
+fn written(property: &Property) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(property.value.trim().trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()
+}
+
+/// The time zone named by the TZID of a property, if it has one, or the name autolink doesn't know.
+fn zone(property: &Property) -> Result<Option<Tz>, String> {
+    match property.params.iter().find(|(name, _)| name == "TZID") {
+        Some((_, tzid)) => tzid.parse::<Tz>().map(Some).map_err(|_| tzid.clone()),
+        None => Ok(None),
+    }
+}
+
+fn format_date_time(at: NaiveDateTime) -> String {
+    at.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// A local time as UTC.
+fn format_utc(at: NaiveDateTime) -> String {
+    let at = chrono::Local.from_local_datetime(&at).earliest().map(|at| at.naive_utc()).unwrap_or(at);
+    format!("{}Z", format_date_time(at))
+}
+
+/// Stays the same for the same entry and time whenever they are written, FNV-1a as std's hashers may change.
+fn uid(name: &str, time: &str) -> String {
+    let hash = format!("{}\n{}", name, time).bytes()
+        .fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    format!("{:016x}@autolink", hash)
+}
+
+/// A link as a URI value, which has no room for spaces, quotes, line breaks and such.
+fn uri(link: &str) -> String {
+    link.trim().chars()
+        .map(|c| match c.is_control() || c.is_whitespace() || "\"<>\\^`{|}".contains(c) {
+            true => c.to_string().bytes().map(|byte| format!("%{:02X}", byte)).collect(),
+            false => c.to_string(),
+        })
+        .collect()
+}
+
+fn find_link(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .find(|word| word.starts_with("https://") || word.starts_with("http://"))
+        .map(|word| word.trim_end_matches(&[')', ',', '.', '>'][..]).to_string())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => (),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn from_code(text: &str) -> Option<Weekday> {
+    [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun]
+        .iter()
+        .cloned()
+        .find(|day| code(*day).eq_ignore_ascii_case(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(events: &[&str]) -> String {
+        let mut lines = vec!["BEGIN:VCALENDAR", "VERSION:2.0"];
+        for event in events {
+            lines.push("BEGIN:VEVENT");
+            lines.extend(event.lines().map(str::trim));
+            lines.push("END:VEVENT");
+        }
+        lines.push("END:VCALENDAR");
+        lines.join("\r\n")
+    }
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2030, month, day)
+    }
+
+    fn local(at: chrono::DateTime<impl TimeZone>) -> NaiveDateTime {
+        at.with_timezone(&chrono::Local).naive_local()
+    }
+
+    #[test]
+    fn a_weekly_event_becomes_weekly_times() {
+        let imported = import(&calendar(&["
+            SUMMARY:Math\\, room 2
+            DTSTART:20300107T103000
+            RRULE:FREQ=WEEKLY;BYDAY=MO,WE;UNTIL=20300630
+            EXDATE:20300114T103000
+            DESCRIPTION:join at https://example.com/math.
+        "])).unwrap();
+        let (plan, settings) = &imported.entries[0];
+        assert_eq!(plan.name, "Math, room 2");
+        assert_eq!(plan.link, "https://example.com/math");
+        let days: Vec<Weekday> = plan.times.iter().map(|timeday| timeday.day).collect();
+        assert_eq!(days, vec![Weekday::Mon, Weekday::Wed]);
+        assert!(plan.times.iter().all(|timeday| timeday.time == NaiveTime::from_hms(10, 30, 0)));
+        assert_eq!(settings.active_from, Some(date(1, 7)));
+        assert_eq!(settings.active_until, Some(date(6, 30)));
+        assert_eq!(settings.skipped, vec![date(1, 14)]);
+    }
+
+    #[test]
+    fn a_count_becomes_the_last_day() {
+        let imported = import(&calendar(&["
+            SUMMARY:Math
+            URL:https://example.com/math
+            DTSTART:20300107T103000
+            RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO;COUNT=3
+        ", "
+            SUMMARY:History
+            URL:https://example.com/history
+            DTSTART:20200106T103000
+            RRULE:FREQ=DAILY;COUNT=5
+        "])).unwrap();
+        assert_eq!(imported.entries.len(), 1);
+        assert_eq!(imported.entries[0].1.active_until, Some(date(2, 4)));
+        assert_eq!(imported.warnings, vec![String::from("left out an event which is over")]);
+    }
+
+    #[test]
+    fn times_in_other_zones_are_moved_here() {
+        let imported = import(&calendar(&["
+            SUMMARY:Tokyo
+            URL:https://example.com/tokyo
+            DTSTART;TZID=Asia/Tokyo:20300107T090000
+        ", "
+            SUMMARY:UTC
+            URL:https://example.com/utc
+            DTSTART:20300107T090000Z
+        ", "
+            SUMMARY:Kiritimati
+            URL:https://example.com/kiritimati
+            DTSTART;TZID=Pacific/Kiritimati:20300107T010000
+            RRULE:FREQ=WEEKLY;BYDAY=MO
+        ", "
+            SUMMARY:Nowhere
+            URL:https://example.com/nowhere
+            DTSTART;TZID=Middle Earth:20300107T090000
+        "])).unwrap();
+        let tokyo = local(chrono_tz::Asia::Tokyo.ymd(2030, 1, 7).and_hms(9, 0, 0));
+        assert_eq!(imported.entries[0].1.once[0].at, tokyo);
+        let utc = local(chrono::Utc.ymd(2030, 1, 7).and_hms(9, 0, 0));
+        assert_eq!(imported.entries[1].1.once[0].at, utc);
+        // Monday morning there may well be Sunday here
+        let kiritimati = local(chrono_tz::Pacific::Kiritimati.ymd(2030, 1, 7).and_hms(1, 0, 0));
+        assert_eq!(imported.entries[2].0.times[0].day, kiritimati.weekday());
+        assert_eq!(imported.entries[2].0.times[0].time, kiritimati.time());
+        assert_eq!(imported.entries.len(), 3);
+        assert!(imported.warnings[0].contains("Middle Earth"));
+    }
+
+    #[test]
+    fn written_entries_read_back_the_same() {
+        let plan = Plan {
+            name: String::from("Math, room 2"),
+            link: String::from("https://example.com/math room"),
+            times: vec![TimeDay::new(NaiveTime::from_hms(10, 30, 0), Weekday::Mon)],
+        };
+        let mut settings = Settings::default();
+        settings.plans.entry(plan.name.clone()).or_default().active_until = Some(date(6, 30));
+        let written = export(std::slice::from_ref(&plan), &settings);
+        assert!(written.contains("URL:https://example.com/math%20room"));
+        let rrule = written.lines().find(|line| line.starts_with("RRULE:")).unwrap();
+        assert!(rrule.contains(";UNTIL=") && rrule.ends_with('Z'));
+        let uids = |written: &str| written.lines().filter(|line| line.starts_with("UID:")).map(String::from).collect::<Vec<_>>();
+        assert_eq!(uids(&written), uids(&export(std::slice::from_ref(&plan), &settings)));
+
+        let imported = import(&written).unwrap();
+        let (read, read_settings) = &imported.entries[0];
+        assert_eq!(read.name, plan.name);
+        assert_eq!(read.times.len(), 1);
+        assert!(autolink_lib::check(read, &plan.times[0]));
+        assert_eq!(read_settings.active_until, Some(date(6, 30)));
+    }
+}
@@ -0,0 +1,65 @@
+use autolink_lib::chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/*
+ * every launch is appended to ~/.autolink-history as a line of json,
+ * lines are never rewritten so a crash loses at most the last one
+*/
+pub fn path() -> PathBuf {
+    let mut dir = home::home_dir().unwrap();
+    dir.push(".autolink-history");
+    dir
+}
+
+/// What made a link open.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Trigger {
+    /// The loop, right on time.
+    Schedule,
+    /// The loop, late because the launch was missed.
+    CatchUp,
+    /// The user.
+    Manual,
+}
+
+impl Trigger {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Trigger::Schedule => "schedule",
+            Trigger::CatchUp => "catch up",
+            Trigger::Manual => "manual",
+        }
+    }
+}
+
+/// A link that was opened, or a command that was run, in which case `link` is its command line.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub at: NaiveDateTime,
+    pub name: String,
+    pub link: String,
+    pub trigger: Trigger,
+    /// Why the launch failed, if it did.
+    pub error: Option<String>,
+    /// The exit status of whatever opened the link, if it exited in time.
+    #[serde(default)]
+    pub status: Option<i32>,
+    /// What a command wrote, for launches that ran one.
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+pub fn append(path: &Path, record: &Record) -> std::io::Result<()> {
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(record).unwrap())
+}
+
+/// Reads every record, oldest first, lines that can't be read are left out.
+pub fn read(path: &Path) -> Vec<Record> {
+    std::fs::read_to_string(path).unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
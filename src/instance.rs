@@ -0,0 +1,40 @@
+use fs2::FileExt;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+/*
+ * only one app or daemon may run at a time, otherwise both would
+ * launch every link and write over each other's ~/.autolink
+ * 
+ * the lock is an advisory lock on ~/.autolink.lock, which the system
+ * lets go of when the process exits, even after a crash
+*/
+pub fn path() -> PathBuf {
+    let mut dir = home::home_dir().unwrap();
+    dir.push(".autolink.lock");
+    dir
+}
+
+/// Held for as long as this instance runs, dropping it lets go of the lock.
+pub struct Lock {
+    _file: File,
+}
+
+/// Takes the lock, or says who has it.
+pub fn acquire() -> Result<Lock, String> {
+    // the pid of whoever has the lock is read before anything is written over it
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path())
+        .map_err(|e| format!("couldn't open {}: {}", path().display(), e))?;
+    if file.try_lock_exclusive().is_err() {
+        let mut pid = String::new();
+        let _ = file.read_to_string(&mut pid);
+        return Err(match pid.trim() {
+            "" => String::from("autolink is already running"),
+            pid => format!("autolink is already running with pid {}", pid),
+        })
+    }
+    let _ = file.set_len(0);
+    let _ = write!(file, "{}", std::process::id());
+    Ok(Lock { _file: file })
+}
@@ -0,0 +1,223 @@
+use autolink_lib::Plan;
+use crossbeam_channel::Sender;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use crate::scheduler::SchedulerCommand;
+use crate::settings::Action;
+use crate::store;
+
+/// How long a client may say nothing before it is hung up on.
+#[cfg(unix)]
+const IDLE: std::time::Duration = std::time::Duration::from_secs(30);
+
+/*
+ * a running app or daemon listens on ~/.autolink.sock, so that other
+ * tools change the plans it has in memory instead of the file under it,
+ * those are the plans of the profile the loop runs
+ * 
+ * every request and every response is a line of json, like
+ * {"command": "launch", "entry": "maths"} or {"ok": true, "error": null, "plans": []}
+*/
+pub fn path() -> PathBuf {
+    let mut dir = home::home_dir().unwrap();
+    dir.push(".autolink.sock");
+    dir
+}
+
+/// The plans the socket works on, which are swapped for others when the loop runs another profile.
+pub type Target = Arc<Mutex<Arc<Mutex<Vec<Plan>>>>>;
+
+/// An entry to launch is given by its name or its index, like on the command line,
+/// one to change is given as it was last listed, so that changes made since aren't overwritten.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    List,
+    /// `actions` are what the entry does after opening its link, if anything.
+    Add {
+        plan: Plan,
+        #[serde(default)]
+        actions: Option<Vec<Action>>,
+    },
+    /// Replaces an entry, the settings of a renamed entry go along with it,
+    /// and its actions are only replaced if they are given.
+    Edit {
+        old: Plan,
+        plan: Plan,
+        #[serde(default)]
+        actions: Option<Vec<Action>>,
+    },
+    /// Removes an entry and its settings.
+    Remove { old: Plan },
+    Start,
+    Stop,
+    Launch { entry: String },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    pub error: Option<String>,
+    /// Every entry after the request, only filled in for `list`.
+    #[serde(default)]
+    pub plans: Vec<Plan>,
+}
+
+impl Response {
+    fn ok(plans: Vec<Plan>) -> Self {
+        Self { ok: true, error: None, plans }
+    }
+
+    fn error(e: String) -> Self {
+        Self { ok: false, error: Some(e), plans: Vec::new() }
+    }
+}
+
+fn respond(request: Request, target: &Target, commands: &Sender<SchedulerCommand>) -> Response {
+    let plans = target.lock().unwrap().clone();
+    let mut plans = plans.lock().unwrap();
+    let changed = match request {
+        Request::List => return Response::ok(plans.clone()),
+        Request::Add { plan, actions } => match store::check_name(&plans, &plan.name, None) {
+            Ok(()) => {
+                let _ = commands.send(SchedulerCommand::SetActions { name: plan.name.clone(), actions: actions.unwrap_or_default() });
+                plans.push(plan);
+                true
+            },
+            Err(e) => return Response::error(e),
+        },
+        Request::Edit { old, plan, actions } => match find_unchanged(&plans, &old) {
+            Ok(i) => match store::check_name(&plans, &plan.name, Some(i)) {
+                Ok(()) => {
+                    if plan.name != old.name {
+                        let _ = commands.send(SchedulerCommand::Rename { from: old.name, to: plan.name.clone() });
+                    }
+                    if let Some(actions) = actions {
+                        let _ = commands.send(SchedulerCommand::SetActions { name: plan.name.clone(), actions });
+                    }
+                    plans[i] = plan;
+                    true
+                },
+                Err(e) => return Response::error(e),
+            },
+            Err(e) => return Response::error(e),
+        },
+        Request::Remove { old } => match find_unchanged(&plans, &old) {
+            Ok(i) => {
+                plans.remove(i);
+                let _ = commands.send(SchedulerCommand::Forget(old.name));
+                true
+            },
+            Err(e) => return Response::error(e),
+        },
+        Request::Start => {
+            let _ = commands.send(SchedulerCommand::Start);
+            false
+        },
+        Request::Stop => {
+            let _ = commands.send(SchedulerCommand::Stop);
+            false
+        },
+        Request::Launch { entry } => match store::find(&plans, &entry) {
+            Ok(i) => {
+                let _ = commands.send(SchedulerCommand::LaunchNow(plans[i].clone()));
+                false
+            },
+            Err(e) => return Response::error(e),
+        },
+    };
+    if changed {
+        let _ = commands.send(SchedulerCommand::ReloadPlans(plans.clone()));
+    }
+    Response::ok(Vec::new())
+}
+
+/// Finds an entry by its name, as long as it hasn't changed since the sender saw it.
+fn find_unchanged(plans: &[Plan], old: &Plan) -> Result<usize, String> {
+    match plans.iter().position(|plan| plan.name == old.name) {
+        Some(i) if store::same_plan(&plans[i], old) => Ok(i),
+        Some(_) => Err(format!("{} was changed in the meantime, have another look at it", old.name)),
+        None => Err(format!("there is no entry called {}", old.name)),
+    }
+}
+
+/// Starts listening in the background, the plans are shared with whoever else uses them.
+#[cfg(unix)]
+pub fn serve(target: Target, commands: Sender<SchedulerCommand>) -> std::io::Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    // a socket left over from a crash would keep bind from working
+    let _ = std::fs::remove_file(path());
+    let listener = UnixListener::bind(path())?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // a client that says nothing mustn't keep the others waiting
+            let (target, commands) = (target.clone(), commands.clone());
+            std::thread::spawn(move || answer(stream, &target, &commands));
+        }
+    });
+    Ok(())
+}
+
+/// Answers the requests of one client, one per line, until it hangs up or says nothing for a while.
+#[cfg(unix)]
+fn answer(mut stream: std::os::unix::net::UnixStream, target: &Target, commands: &Sender<SchedulerCommand>) {
+    use std::io::{BufRead, BufReader, Write};
+
+    let _ = stream.set_read_timeout(Some(IDLE));
+    let reader = match stream.try_clone() {
+        Ok(reader) => BufReader::new(reader),
+        Err(_) => return,
+    };
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => respond(request, target, commands),
+            Err(e) => Response::error(format!("couldn't read the request: {}", e)),
+        };
+        if writeln!(stream, "{}", serde_json::to_string(&response).unwrap()).is_err() {
+            break
+        }
+    }
+}
+
+/// Sends a request to the running app or daemon, gives None if neither is running.
+#[cfg(unix)]
+pub fn send(request: &Request) -> Option<Result<Response, String>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(path()).ok()?;
+    let mut exchange = || -> std::io::Result<String> {
+        writeln!(stream, "{}", serde_json::to_string(request).unwrap())?;
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line)?;
+        Ok(line)
+    };
+    Some(exchange()
+        .map_err(|e| e.to_string())
+        .and_then(|line| serde_json::from_str(&line).map_err(|e| e.to_string())))
+}
+
+#[cfg(not(unix))]
+pub fn serve(_target: Target, _commands: Sender<SchedulerCommand>) -> std::io::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn send(_request: &Request) -> Option<Result<Response, String>> {
+    None
+}
+
+/// Removes the socket, once nothing listens on it anymore.
+pub fn close() {
+    let _ = std::fs::remove_file(path());
+}
@@ -0,0 +1,254 @@
+use autolink_lib::Plan;
+use crossbeam_channel::Receiver;
+use std::io::Read;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+use crate::settings::{Action, ActionKind, Launcher, PlanSettings, Program};
+
+/// How long a launcher is waited on for its exit status,
+/// a browser that wasn't open yet keeps running and is left to it.
+const WAIT: Duration = Duration::from_secs(10);
+
+/// How much of stdout and of stderr of a program goes to the history, in bytes.
+const MAX_OUTPUT: u64 = 4096;
+
+/// How opening a link went.
+pub struct Outcome {
+    /// The exit status of the launcher, if it exited in time and wasn't killed.
+    pub status: Option<i32>,
+    /// Why the link didn't open or the program failed, if it did.
+    pub error: Option<String>,
+    /// What a program wrote to stdout and then to stderr, if anything.
+    pub output: Option<String>,
+}
+
+impl Outcome {
+    fn exited(what: &str, status: ExitStatus) -> Self {
+        Self {
+            status: status.code(),
+            output: None,
+            error: match status.success() {
+                true => None,
+                false => Some(format!("{} exited with {}", what, status)),
+            },
+        }
+    }
+
+    fn failed(error: String) -> Self {
+        Self { status: None, error: Some(error), output: None }
+    }
+}
+
+/// Opens a link with the default browser of the system, the way autolink always has,
+/// autolink_lib doesn't say whether it worked so it is taken to have.
+pub fn open(link: &str) -> Outcome {
+    autolink_lib::open_link(link);
+    Outcome { status: None, error: None, output: None }
+}
+
+/// Opens a link with a launcher, waiting a little for it to exit.
+pub fn open_with(launcher: &Launcher, link: &str) -> Outcome {
+    let mut command = match launcher {
+        Launcher::System => return open(link),
+        Launcher::Browser { program, args } => {
+            let mut command = Command::new(program);
+            command.args(args).arg(link);
+            command
+        },
+        Launcher::Command { template } => {
+            // the link goes in after splitting, so that it stays one argument whatever is in it
+            let words = match split(template) {
+                Ok(words) => words,
+                Err(e) => return Outcome::failed(format!("couldn't read {}: {}", template, e)),
+            };
+            let mut words = words.into_iter().map(|word| word.replace("{link}", link));
+            let mut command = Command::new(words.next().unwrap_or_default());
+            command.args(words);
+            command
+        },
+    };
+    match command.spawn() {
+        Ok(child) => wait(child),
+        Err(e) => Outcome::failed(format!("couldn't run {:?}: {}", command, e)),
+    }
+}
+
+/// Splits a command line into words the way a shell does, what is in quotes stays together
+/// and `\` keeps the character after it as it is, except in single quotes.
+pub fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => match chars.next() {
+                Some(next) => word.get_or_insert_with(String::new).push(next),
+                None => return Err(String::from("it ends with a \\")),
+            },
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            },
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(open) = quote {
+        return Err(format!("a {} isn't closed", open))
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Puts words back into a line that `split` gives them back from.
+pub fn join(words: &[String]) -> String {
+    words.iter()
+        .map(|word| match word.is_empty() || word.contains(|c: char| c.is_whitespace() || "'\"\\".contains(c)) {
+            true => format!("'{}'", word.replace('\'', "'\\''")),
+            false => word.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn wait(mut child: Child) -> Outcome {
+    let started = Instant::now();
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Outcome::exited("the launcher", status),
+            Ok(None) if started.elapsed() < WAIT => std::thread::sleep(Duration::from_millis(100)),
+            Ok(None) => {
+                // it is still waited on, so that it doesn't linger once it exits
+                std::thread::spawn(move || child.wait());
+                return Outcome { status: None, error: None, output: None }
+            },
+            Err(e) => return Outcome::failed(e.to_string()),
+        }
+    }
+}
+
+/// Runs a program until it exits, or until its timeout runs out and it is killed.
+pub fn run(program: &Program) -> Outcome {
+    let mut command = Command::new(&program.program);
+    command.args(&program.args)
+        .envs(program.env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = &program.dir {
+        command.current_dir(dir);
+    }
+    // whatever it starts goes in its own group, so that a timeout kills that too
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(e) => return Outcome::failed(format!("couldn't run {}: {}", program.program, e)),
+    };
+    // a program that fills up a pipe nobody reads would never exit
+    let stdout = read(child.stdout.take());
+    let stderr = read(child.stderr.take());
+    let started = Instant::now();
+    let mut outcome = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Outcome::exited(&program.program, status),
+            Ok(None) if started.elapsed() < Duration::from_secs(program.timeout_secs) => {
+                std::thread::sleep(Duration::from_millis(100));
+            },
+            Ok(None) => {
+                kill(&mut child);
+                let _ = child.wait();
+                break Outcome::failed(format!("{} was killed after {} seconds", program.program, program.timeout_secs))
+            },
+            Err(e) => break Outcome::failed(e.to_string()),
+        }
+    };
+    // something it started in the background may still hold the pipes open
+    let output: String = [stdout, stderr].iter()
+        .filter_map(|output| output.recv_timeout(Duration::from_secs(1)).ok())
+        .collect();
+    if output.trim() != "" {
+        outcome.output = Some(output.trim().to_string());
+    }
+    outcome
+}
+
+/// Kills a program run by `run` along with whatever it started.
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    // the group has the id of the program
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Reads up to `MAX_OUTPUT` bytes of a pipe on another thread, throwing the rest away.
+fn read(pipe: Option<impl Read + Send + 'static>) -> Receiver<String> {
+    let (sender, receiver) = crossbeam_channel::bounded(1);
+    if let Some(mut pipe) = pipe {
+        std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            let _ = pipe.by_ref().take(MAX_OUTPUT).read_to_end(&mut bytes);
+            let _ = std::io::copy(&mut pipe, &mut std::io::sink());
+            let _ = sender.send(String::from_utf8_lossy(&bytes).into_owned());
+        });
+    }
+    receiver
+}
+
+/// Everything a plan does in the order it does it, opening its own link first.
+pub fn actions(plan: &Plan, settings: &PlanSettings) -> Vec<Action> {
+    let mut actions = vec![Action { delay_secs: 0, kind: ActionKind::Link { url: plan.link.clone() } }];
+    actions.extend(settings.actions.iter().cloned());
+    actions
+}
+
+/// Does everything a plan does, waiting out the delay before each action,
+/// links are opened with the launcher of the plan and `done` is told how each action went.
+pub fn open_all(plan: &Plan, settings: &PlanSettings, mut done: impl FnMut(&Action, Outcome)) {
+    for action in actions(plan, settings) {
+        std::thread::sleep(Duration::from_secs(action.delay_secs));
+        let outcome = match &action.kind {
+            ActionKind::Link { url } => open_with(&settings.launcher, url),
+            ActionKind::Command(program) => run(program),
+        };
+        done(&action, outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_words_stay_together() {
+        let words = split(r#"-P "work profile" --name='it'\''s' a\ b"#).unwrap();
+        assert_eq!(words, vec!["-P", "work profile", "--name=it's", "a b"]);
+        assert_eq!(split(&join(&words)).unwrap(), words);
+        assert_eq!(split(r#"'' """#).unwrap(), vec!["", ""]);
+        assert!(split("\"open").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_timeout_kills_what_the_program_started() {
+        let program = Program {
+            program: String::from("sh"),
+            args: vec![String::from("-c"), String::from("sleep 30 & echo started; wait")],
+            timeout_secs: 1,
+            ..Program::default()
+        };
+        let outcome = run(&program);
+        assert!(outcome.error.unwrap().contains("killed"));
+        // the sleep would keep stdout open, and the output unread, if it were still running
+        assert_eq!(outcome.output.as_deref(), Some("started"));
+    }
+}
@@ -1,12 +1,54 @@
 extern crate eframe;
 use eframe::{egui, epi};
 mod app;
+mod cli;
+mod daemon;
+mod exchange;
+mod history;
+mod instance;
+mod ipc;
+mod launch;
+mod profiles;
+mod scheduler;
+mod settings;
+mod store;
 
 use app::App;
 
 use crossbeam_channel;
 
 fn main() {
+    let matches = cli::app().get_matches();
+    if let (command, Some(args)) = matches.subcommand() {
+        if let Err(e) = cli::run(command, args) {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return
+    }
+    let lock = match instance::acquire() {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{}, use the command line to talk to it, like `autolink-egui list`", e);
+            std::process::exit(1);
+        },
+    };
+    if let Some(profile) = matches.value_of("profile") {
+        if !profiles::exists(profile) {
+            eprintln!("there is no profile called {}", profile);
+            std::process::exit(1);
+        }
+        profiles::set_running(profile);
+    }
+    for profile in profiles::list() {
+        if let Err(e) = store::migrate(&profile, &lock) {
+            eprintln!("{}", e);
+        }
+    }
+    if matches.is_present("headless") {
+        daemon::run(&profiles::running());
+        return
+    }
     let app = App::default();
     eframe::run_native(Box::new(app), epi::NativeOptions {
         always_on_top: false,
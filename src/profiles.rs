@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+use crate::store;
+
+/// The profile there is before any other is made, it has no file of its own.
+pub const DEFAULT: &str = "default";
+
+/*
+ * a profile is a set of entries with their settings, like one for each semester
+ * 
+ * the default profile lives in ~/.autolink and ~/.autolink-settings as it always has,
+ * every other one in ~/.autolink-profiles/<name> and ~/.autolink-profiles/<name>.settings
+ * 
+ * the loop runs one profile at a time, the one named in ~/.autolink-profile
+*/
+fn home() -> PathBuf {
+    home::home_dir().unwrap()
+}
+
+fn dir(base: &Path) -> PathBuf {
+    base.join(".autolink-profiles")
+}
+
+pub fn plans_path(name: &str) -> PathBuf {
+    plans_path_in(&home(), name)
+}
+
+/// Where the plans of a profile are kept, with `base` in place of the home folder.
+pub fn plans_path_in(base: &Path, name: &str) -> PathBuf {
+    match name {
+        DEFAULT => base.join(".autolink"),
+        name => dir(base).join(name),
+    }
+}
+
+pub fn settings_path(name: &str) -> PathBuf {
+    settings_path_in(&home(), name)
+}
+
+pub fn settings_path_in(base: &Path, name: &str) -> PathBuf {
+    match name {
+        DEFAULT => base.join(".autolink-settings"),
+        name => dir(base).join(format!("{}.settings", name)),
+    }
+}
+
+/// Every profile, the default one first.
+pub fn list() -> Vec<String> {
+    list_in(&home())
+}
+
+pub fn list_in(base: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir(base)).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        // backups, settings and such have an extension, profiles don't
+        .filter(|name| !name.contains('.'))
+        .collect();
+    names.sort();
+    names.insert(0, String::from(DEFAULT));
+    names
+}
+
+pub fn exists(name: &str) -> bool {
+    exists_in(&home(), name)
+}
+
+fn exists_in(base: &Path, name: &str) -> bool {
+    name == DEFAULT || plans_path_in(base, name).exists()
+}
+
+/// The profile the loop runs.
+pub fn running() -> String {
+    std::fs::read_to_string(home().join(".autolink-profile")).ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| exists(name))
+        .unwrap_or(String::from(DEFAULT))
+}
+
+pub fn set_running(name: &str) {
+    let _ = std::fs::write(home().join(".autolink-profile"), name);
+}
+
+/// Makes a profile with no entries.
+pub fn create(name: &str) -> Result<(), String> {
+    create_in(&home(), name)
+}
+
+pub fn create_in(base: &Path, name: &str) -> Result<(), String> {
+    let valid = name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == ' ');
+    if name.trim().is_empty() || !valid {
+        return Err(String::from("a profile name can only have letters, numbers, spaces, - and _"))
+    }
+    if exists_in(base, name) {
+        return Err(format!("there is a profile called {} already", name))
+    }
+    std::fs::create_dir_all(dir(base)).map_err(|e| format!("couldn't make {}: {}", dir(base).display(), e))?;
+    store::save_to(&plans_path_in(base, name), Vec::new())
+}
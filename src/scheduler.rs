@@ -0,0 +1,470 @@
+use autolink_lib::Plan;
+use autolink_lib::chrono;
+use chrono::NaiveDateTime;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::settings::{Settings, CatchUp};
+use crate::history::{self, Trigger, Record};
+use crate::{launch, store};
+
+pub mod clock;
+mod command;
+pub mod occurrence;
+
+pub use clock::{Clock, SystemClock};
+pub use command::{SchedulerCommand, SchedulerEvent};
+
+/// The longest the thread sleeps, so that it notices the clock jumping soon enough.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// Launches that were missed by more than this are forgotten rather than caught up on.
+const CATCH_UP_DAYS: i64 = 7;
+
+/*
+ * the scheduler remembers up to when it has checked the plans,
+ * every check launches whatever was due between then and now
+ * and moves that mark to now, so nothing is launched twice
+ * 
+ * between checks the thread sleeps until the next launch is due,
+ * or until a command comes in
+ * 
+ * a launch that was due more than a minute ago was missed,
+ * the catch up setting of its plan decides what happens to it
+ * 
+ * nothing is launched in the quiet times of the settings, launches due in them are left out
+ * like those on holidays, and missed ones aren't caught up on in them
+*/
+pub struct Scheduler<C: Clock> {
+    clock: C,
+    plans: Vec<Plan>,
+    settings: Settings,
+    checked_until: NaiveDateTime,
+    resume_from: Option<NaiveDateTime>,
+    is_running: bool,
+    paused_until: Option<NaiveDateTime>,
+    history: Option<PathBuf>,
+}
+
+/// A launch that has become due.
+pub struct Due {
+    pub plan: Plan,
+    pub at: NaiveDateTime,
+}
+
+impl<C: Clock> Scheduler<C> {
+    pub fn new(clock: C) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            plans: Vec::new(),
+            settings: Settings::default(),
+            checked_until: now,
+            resume_from: None,
+            is_running: false,
+            paused_until: None,
+            history: None,
+        }
+    }
+
+    /// Makes every launch get appended to the history at `path`.
+    pub fn record_to(&mut self, path: PathBuf) {
+        self.history = Some(path);
+    }
+
+    pub fn reload(&mut self, plans: Vec<Plan>, settings: Settings) {
+        self.plans = plans;
+        self.settings = settings;
+    }
+
+    /// Makes the next start catch up on what was missed since `checked_until`,
+    /// instead of only looking at what is due from then on.
+    pub fn resume_from(&mut self, checked_until: NaiveDateTime) {
+        self.resume_from = Some(checked_until);
+    }
+
+    #[cfg(test)]
+    pub fn plans(&self) -> &[Plan] {
+        &self.plans
+    }
+
+    #[cfg(test)]
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    #[cfg(test)]
+    pub fn paused_until(&self) -> Option<NaiveDateTime> {
+        self.paused_until
+    }
+
+    /// The next launch of any plan that hasn't been checked yet.
+    pub fn next_due(&self) -> Option<NaiveDateTime> {
+        self.plans.iter()
+            .filter_map(|plan| occurrence::next_after(plan, &self.settings, self.checked_until))
+            .min()
+    }
+
+    /// Returns the launches that have become due since the last tick, in order.
+    pub fn tick(&mut self) -> Vec<Due> {
+        let now = self.clock.now();
+        let after = self.checked_until.max(now - chrono::Duration::days(CATCH_UP_DAYS));
+        let mut due = Vec::new();
+        for plan in &self.plans {
+            for at in occurrence::between(plan, &self.settings, after, now) {
+                due.push(Due { plan: plan.clone(), at });
+            }
+        }
+        due.sort_by_key(|due| due.at);
+        if now > self.checked_until {
+            self.checked_until = now;
+        }
+        due
+    }
+
+    /// How long the thread may sleep before something needs to be checked.
+    fn timeout(&self) -> Duration {
+        let now = self.clock.now();
+        let mut wake = now + chrono::Duration::from_std(MAX_SLEEP).unwrap();
+        if let Some(due) = self.next_due() {
+            wake = wake.min(due);
+        }
+        if let Some(until) = self.paused_until {
+            wake = wake.min(until);
+        }
+        (wake - now).to_std().unwrap_or(Duration::from_secs(0))
+    }
+
+    /// Applies a command, returns false once the scheduler has been shut down.
+    pub fn handle(&mut self, command: SchedulerCommand, events: &Sender<SchedulerEvent>) -> bool {
+        match command {
+            SchedulerCommand::Start => {
+                let now = self.clock.now();
+                self.checked_until = self.resume_from.take()
+                    .filter(|checked_until| *checked_until < now)
+                    .unwrap_or(now);
+                self.is_running = true;
+                let _ = events.send(SchedulerEvent::Started);
+            },
+            SchedulerCommand::Stop => {
+                self.is_running = false;
+                let _ = events.send(SchedulerEvent::Stopped);
+            },
+            SchedulerCommand::Reload(plans, settings) => {
+                let length = plans.len();
+                self.reload(plans, settings);
+                let _ = events.send(SchedulerEvent::Reloaded(length));
+            },
+            SchedulerCommand::ReloadPlans(plans) => {
+                let length = plans.len();
+                self.plans = plans;
+                let _ = events.send(SchedulerEvent::Reloaded(length));
+            },
+            SchedulerCommand::Rename { from, to } => {
+                self.settings.rename(&from, &to);
+                let _ = events.send(SchedulerEvent::Renamed { from, to });
+            },
+            SchedulerCommand::Forget(name) => {
+                self.settings.plans.remove(&name);
+                let _ = events.send(SchedulerEvent::Forgotten(name));
+            },
+            SchedulerCommand::SetActions { name, actions } => {
+                self.settings.plans.entry(name.clone()).or_default().actions = actions.clone();
+                let _ = events.send(SchedulerEvent::ActionsSet { name, actions });
+            },
+            SchedulerCommand::PauseUntil(until) => {
+                self.paused_until = Some(until);
+                let _ = events.send(SchedulerEvent::Paused(until));
+            },
+            SchedulerCommand::LaunchNow(plan) => {
+                // whoever asked may have seen the plans before they changed
+                match self.plans.iter().any(|known| store::same_plan(known, &plan)) {
+                    true => self.launch(plan, Trigger::Manual, events),
+                    false => {
+                        let _ = events.send(SchedulerEvent::Gone { name: plan.name });
+                    },
+                }
+            },
+            SchedulerCommand::Shutdown => {
+                let checked_until = match self.is_running {
+                    true => Some(self.checked_until),
+                    false => None,
+                };
+                self.is_running = false;
+                let _ = events.send(SchedulerEvent::ShutDown { checked_until });
+                return false
+            },
+        }
+        true
+    }
+
+    /// Launches whatever is due, unless the loop is stopped or paused.
+    pub fn check(&mut self, events: &Sender<SchedulerEvent>) {
+        if !self.is_running {
+            return
+        }
+        let now = self.clock.now();
+        let slept = now - self.checked_until;
+        if slept < chrono::Duration::zero() || slept > chrono::Duration::from_std(MAX_SLEEP).unwrap() + chrono::Duration::minutes(1) {
+            let _ = events.send(SchedulerEvent::ClockJumped { from: self.checked_until, to: now });
+        }
+        if let Some(until) = self.paused_until {
+            if now < until {
+                // whatever was due during the pause is dropped
+                self.tick();
+                self.mark_done(events);
+                return
+            }
+            self.paused_until = None;
+            let _ = events.send(SchedulerEvent::Resumed);
+        }
+        for Due { plan, at } in self.tick() {
+            if self.settings.is_quiet(now) {
+                let _ = events.send(SchedulerEvent::Skipped { plan, at, quiet: true });
+                continue
+            }
+            let late = now - at;
+            if late < chrono::Duration::minutes(1) {
+                self.launch(plan, Trigger::Schedule, events);
+                continue
+            }
+            match self.settings.plan(&plan.name).catch_up {
+                CatchUp::Launch { grace_minutes } if late <= chrono::Duration::minutes(grace_minutes) => {
+                    self.launch(plan, Trigger::CatchUp, events);
+                },
+                CatchUp::Ask => {
+                    let _ = events.send(SchedulerEvent::Missed { plan, at });
+                },
+                _ => {
+                    let _ = events.send(SchedulerEvent::Skipped { plan, at, quiet: false });
+                },
+            }
+        }
+        self.mark_done(events);
+    }
+
+    /// Marks the launches on a date that have been checked as done.
+    fn mark_done(&mut self, events: &Sender<SchedulerEvent>) {
+        for (name, settings) in self.settings.plans.iter_mut() {
+            for once in settings.once.iter_mut() {
+                if !once.done && once.at <= self.checked_until {
+                    once.done = true;
+                    let _ = events.send(SchedulerEvent::OnceDone { name: name.clone(), at: once.at });
+                }
+            }
+        }
+    }
+
+    /// Does what a plan does on another thread, so that its delays and commands don't hold up the loop.
+    fn launch(&self, plan: Plan, trigger: Trigger, events: &Sender<SchedulerEvent>) {
+        let settings = self.settings.plan(&plan.name);
+        let history = self.history.clone();
+        let events = events.clone();
+        let clock = self.clock.clone();
+        std::thread::spawn(move || launch::open_all(&plan, &settings, |action, outcome| {
+            // when it was done, the delays aside, a browser or a command may have taken a while
+            let at = clock.now();
+            let error = outcome.error;
+            if let Some(path) = &history {
+                let record = Record {
+                    at,
+                    name: plan.name.clone(),
+                    link: action.label(),
+                    trigger,
+                    error: error.clone(),
+                    status: outcome.status,
+                    output: outcome.output,
+                };
+                if let Err(e) = history::append(path, &record) {
+                    let _ = events.send(SchedulerEvent::HistoryFailed(format!("couldn't write to the history: {}", e)));
+                }
+            }
+            let _ = events.send(SchedulerEvent::Launched { plan: plan.clone(), link: action.label(), at, error });
+        }));
+    }
+
+    /// The loop of the scheduler thread, returns after a shutdown command
+    /// or once the app has hung up.
+    pub fn run(mut self, commands: Receiver<SchedulerCommand>, events: Sender<SchedulerEvent>) {
+        loop {
+            let command = match self.is_running {
+                true => commands.recv_timeout(self.timeout()),
+                false => commands.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match command {
+                Ok(command) => if !self.handle(command, &events) {
+                    return
+                },
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            self.check(&events);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autolink_lib::TimeDay;
+    use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+    use clock::FakeClock;
+
+    fn math() -> Plan {
+        Plan {
+            name: String::from("math"),
+            link: String::from("https://example.com/math"),
+            times: vec![
+                TimeDay::new(NaiveTime::from_hms(10, 30, 0), Weekday::Wed),
+                TimeDay::new(NaiveTime::from_hms(13, 15, 0), Weekday::Fri),
+            ],
+        }
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2024, 1, day).and_hms(hour, minute, 0)
+    }
+
+    /// A scheduler on a fake clock with math loaded, and a clone of the clock to move it with.
+    fn scheduler(now: NaiveDateTime, catch_up: CatchUp) -> (Scheduler<FakeClock>, FakeClock) {
+        let clock = FakeClock::new(now);
+        let mut scheduler = Scheduler::new(clock.clone());
+        let mut settings = Settings::default();
+        settings.plans.entry(String::from("math")).or_default().catch_up = catch_up;
+        scheduler.reload(vec![math()], settings);
+        (scheduler, clock)
+    }
+
+    /*
+     * the loop used to take each time off a copy of the plans once autolink_lib::check
+     * found it due, and to fill the copy up again from the plans once it had no times left
+     * 
+     * launches are worked out now instead, this is the old way kept to test against
+    */
+    struct Consumed {
+        plans: Vec<Plan>,
+        remaining: Vec<Plan>,
+    }
+
+    impl Consumed {
+        fn tick(&mut self, now: NaiveDateTime) -> Vec<String> {
+            let timeday = TimeDay::new(now.time(), now.weekday());
+            let mut due = Vec::new();
+            for plan in self.remaining.iter_mut() {
+                if autolink_lib::check(plan, &timeday) {
+                    due.push(plan.name.clone());
+                    plan.remove_matching_time(&timeday);
+                }
+            }
+            if self.remaining.iter().all(|plan| plan.times.is_empty()) {
+                self.remaining = self.plans.clone();
+            }
+            due
+        }
+    }
+
+    #[test]
+    fn launches_are_those_autolink_lib_checks_once_a_week() {
+        let art = Plan {
+            name: String::from("art"),
+            link: String::from("https://example.com/art"),
+            times: vec![
+                TimeDay::new(NaiveTime::from_hms(9, 0, 0), Weekday::Mon),
+                TimeDay::new(NaiveTime::from_hms(10, 30, 0), Weekday::Wed),
+            ],
+        };
+        let plans = vec![math(), art];
+        let clock = FakeClock::new(at(1, 0, 0));
+        let mut scheduler = Scheduler::new(clock.clone());
+        scheduler.reload(plans.clone(), Settings::default());
+        let mut consumed = Consumed { plans: plans.clone(), remaining: plans };
+        let mut launches = 0;
+        // two weeks a minute at a time, so that every time is used up and comes back
+        for _ in 0..14 * 24 * 60 {
+            clock.advance(chrono::Duration::minutes(1));
+            let due: Vec<String> = scheduler.tick().into_iter().map(|due| due.plan.name).collect();
+            assert_eq!(due, consumed.tick(clock.now()), "at {}", clock.now());
+            launches += due.len();
+        }
+        assert_eq!(launches, 8);
+    }
+
+    #[test]
+    fn a_due_launch_comes_once() {
+        let (mut scheduler, clock) = scheduler(at(3, 10, 0), CatchUp::default());
+        assert_eq!(scheduler.plans().len(), 1);
+        clock.set(at(3, 10, 29));
+        assert!(scheduler.tick().is_empty());
+        clock.advance(chrono::Duration::minutes(2));
+        let due = scheduler.tick();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].at, at(3, 10, 30));
+        assert!(scheduler.tick().is_empty());
+    }
+
+    #[test]
+    fn the_week_rolls_over() {
+        let (mut scheduler, clock) = scheduler(at(5, 14, 0), CatchUp::default());
+        assert_eq!(scheduler.next_due(), Some(at(10, 10, 30)));
+        clock.set(at(10, 10, 31));
+        let due = scheduler.tick();
+        assert_eq!(due.iter().map(|due| due.at).collect::<Vec<_>>(), vec![at(10, 10, 30)]);
+        assert_eq!(scheduler.next_due(), Some(at(12, 13, 15)));
+    }
+
+    #[test]
+    fn nothing_is_launched_while_paused() {
+        let (mut scheduler, clock) = scheduler(at(3, 9, 0), CatchUp::Ask);
+        let (sender, events) = crossbeam_channel::unbounded();
+        scheduler.handle(SchedulerCommand::Start, &sender);
+        scheduler.handle(SchedulerCommand::PauseUntil(at(4, 0, 0)), &sender);
+        assert!(scheduler.is_running());
+        clock.set(at(3, 10, 31));
+        scheduler.check(&sender);
+        assert_eq!(scheduler.paused_until(), Some(at(4, 0, 0)));
+        assert!(!events.try_iter().any(|event| matches!(event, SchedulerEvent::Launched { .. } | SchedulerEvent::Missed { .. })));
+        // what was due during the pause is dropped, what was missed after it is not
+        clock.set(at(5, 13, 20));
+        scheduler.check(&sender);
+        assert_eq!(scheduler.paused_until(), None);
+        let events: Vec<_> = events.try_iter().collect();
+        assert!(matches!(events[0], SchedulerEvent::ClockJumped { .. }));
+        assert!(matches!(events[1], SchedulerEvent::Resumed));
+        match &events[2] {
+            SchedulerEvent::Missed { plan, at: missed } => {
+                assert_eq!(plan.name, "math");
+                assert_eq!(*missed, at(5, 13, 15));
+            },
+            _ => panic!("math wasn't missed"),
+        }
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn missed_launches_go_by_the_catch_up_setting() {
+        for (catch_up, asked) in [(CatchUp::Ask, true), (CatchUp::Skip, false), (CatchUp::Launch { grace_minutes: 15 }, false)] {
+            let (mut scheduler, _clock) = scheduler(at(3, 12, 0), catch_up);
+            let (sender, events) = crossbeam_channel::unbounded();
+            scheduler.resume_from(at(3, 9, 0));
+            scheduler.handle(SchedulerCommand::Start, &sender);
+            scheduler.check(&sender);
+            let events: Vec<_> = events.try_iter()
+                .filter(|event| !matches!(event, SchedulerEvent::ClockJumped { .. }))
+                .collect();
+            assert!(matches!(events[0], SchedulerEvent::Started));
+            // an hour and a half late is past the grace of those launched late
+            match &events[1] {
+                SchedulerEvent::Missed { at: missed, .. } => {
+                    assert!(asked);
+                    assert_eq!(*missed, at(3, 10, 30));
+                },
+                SchedulerEvent::Skipped { at: skipped, quiet, .. } => {
+                    assert!(!asked && !quiet);
+                    assert_eq!(*skipped, at(3, 10, 30));
+                },
+                _ => panic!("math was neither missed nor skipped"),
+            }
+            assert_eq!(events.len(), 2);
+        }
+    }
+}
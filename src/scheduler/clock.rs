@@ -0,0 +1,58 @@
+use autolink_lib::chrono;
+use chrono::NaiveDateTime;
+#[cfg(test)]
+use std::sync::{Arc, Mutex};
+
+/*
+ * the scheduler never asks chrono for the time directly,
+ * it asks a clock, so that the time can be faked
+ * 
+ * launches run on threads of their own, which take a clone of the clock along
+*/
+pub trait Clock: Clone + Send + 'static {
+    fn now(&self) -> NaiveDateTime;
+}
+
+/// The local wall clock, this is what the app runs on.
+#[derive(Clone)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        chrono::Local::now().naive_local()
+    }
+}
+
+/// A clock that only moves when it is told to.
+/// Clones share the same time, so a clone can be kept around
+/// to move the clock of a scheduler that owns the original.
+#[cfg(test)]
+#[derive(Clone)]
+pub struct FakeClock {
+    now: Arc<Mutex<NaiveDateTime>>,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: NaiveDateTime) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: chrono::Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += by;
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> NaiveDateTime {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,58 @@
+use autolink_lib::Plan;
+use autolink_lib::chrono::NaiveDateTime;
+use crate::settings::{Action, Settings};
+
+/// What the app asks the scheduler thread to do.
+pub enum SchedulerCommand {
+    Start,
+    Stop,
+    /// Replaces the plans of the scheduler and their settings, sent after every add, edit or remove.
+    Reload(Vec<Plan>, Settings),
+    /// Replaces only the plans, for when they were changed by something other than the app.
+    ReloadPlans(Vec<Plan>),
+    /// Moves the settings of a plan renamed by something other than the app.
+    Rename { from: String, to: String },
+    /// Drops the settings of a plan removed by something other than the app.
+    Forget(String),
+    /// Sets what a plan added or changed by something other than the app does after opening its link.
+    SetActions { name: String, actions: Vec<Action> },
+    /// Don't launch anything until the given time, the loop keeps running.
+    PauseUntil(NaiveDateTime),
+    /// Launches the plan right away, even if the loop is stopped,
+    /// as long as the scheduler still has it as it was given.
+    LaunchNow(Plan),
+    Shutdown,
+}
+
+/// What the scheduler thread tells the app it did.
+pub enum SchedulerEvent {
+    Started,
+    Stopped,
+    /// The number of plans the scheduler now has.
+    Reloaded(usize),
+    Paused(NaiveDateTime),
+    Resumed,
+    /// A link of a plan was opened or one of its commands was run, `link` is the command line then,
+    /// `error` is why it failed, if it did.
+    Launched { plan: Plan, link: String, at: NaiveDateTime, error: Option<String> },
+    /// A launch was missed and the plan wants the user to decide, `plan` is for `LaunchNow`.
+    Missed { plan: Plan, at: NaiveDateTime },
+    /// A plan asked to launch was changed or removed since, so it wasn't.
+    Gone { name: String },
+    /// The settings of a plan were moved to its new name, the app keeps its own settings in step.
+    Renamed { from: String, to: String },
+    /// The settings of a removed plan were dropped.
+    Forgotten(String),
+    /// The actions of a plan were set.
+    ActionsSet { name: String, actions: Vec<Action> },
+    /// A launch was missed and has been skipped, or was due in a quiet time.
+    Skipped { plan: Plan, at: NaiveDateTime, quiet: bool },
+    /// A launch couldn't be written to the history.
+    HistoryFailed(String),
+    /// A launch on a date has passed, whether it was launched or not.
+    OnceDone { name: String, at: NaiveDateTime },
+    /// The clock moved further than the thread slept, or moved backwards.
+    ClockJumped { from: NaiveDateTime, to: NaiveDateTime },
+    /// `checked_until` is only set if the loop was running, it can be handed to `Scheduler::resume_from`.
+    ShutDown { checked_until: Option<NaiveDateTime> },
+}
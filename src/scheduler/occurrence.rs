@@ -0,0 +1,194 @@
+use autolink_lib::Plan;
+use autolink_lib::chrono;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use crate::settings::{Settings, PlanSettings};
+
+/// How many days ahead `next_after` looks day by day,
+/// long enough for a monthly repeat to come around.
+const LOOKAHEAD_DAYS: i64 = 62;
+
+/// How many dates of a rule past the lookahead are stepped over for holidays and the like before giving up.
+const MAX_SKIPPED: usize = 100;
+
+/// Whether a plan may launch at all on the given date.
+pub fn is_active(settings: &PlanSettings, holidays: &[NaiveDate], date: NaiveDate) -> bool {
+    settings.enabled
+        && settings.paused_until.iter().all(|until| *until <= date)
+        && !holidays.contains(&date)
+        && !settings.skipped.contains(&date)
+        && settings.active_from.iter().all(|from| *from <= date)
+        && settings.active_until.iter().all(|until| date <= *until)
+}
+
+/// The times a plan launches at on the given date, in order.
+fn times_on(plan: &Plan, settings: &PlanSettings, holidays: &[NaiveDate], date: NaiveDate) -> Vec<NaiveTime> {
+    if !is_active(settings, holidays, date) {
+        return Vec::new()
+    }
+    let mut times: Vec<NaiveTime> = plan.times.iter()
+        .filter(|timeday| timeday.day == date.weekday())
+        .map(|timeday| timeday.time)
+        .collect();
+    times.extend(settings.once.iter()
+        .filter(|once| !once.done && once.at.date() == date)
+        .map(|once| once.at.time()));
+    times.extend(settings.rules.iter()
+        .filter(|rule| rule.repeat.falls_on(date))
+        .map(|rule| rule.time));
+    times.sort();
+    times.dedup();
+    times
+}
+
+/// The launches of a plan after `after` up to and including `until`, in order.
+pub fn between(plan: &Plan, settings: &Settings, after: NaiveDateTime, until: NaiveDateTime) -> Vec<NaiveDateTime> {
+    let plan_settings = settings.plan(&plan.name);
+    let mut launches = Vec::new();
+    let mut date = after.date();
+    while date <= until.date() {
+        for time in times_on(plan, &plan_settings, &settings.holidays, date) {
+            let at = date.and_time(time);
+            if after < at && at <= until && !settings.is_quiet(at) {
+                launches.push(at);
+            }
+        }
+        date = date.succ();
+    }
+    launches
+}
+
+/// The first launch of a plan after `after`.
+pub fn next_after(plan: &Plan, settings: &Settings, after: NaiveDateTime) -> Option<NaiveDateTime> {
+    let plan_settings = settings.plan(&plan.name);
+    let mut date = after.date();
+    for _ in 0..LOOKAHEAD_DAYS {
+        for time in times_on(plan, &plan_settings, &settings.holidays, date) {
+            let at = date.and_time(time);
+            if after < at && !settings.is_quiet(at) {
+                return Some(at)
+            }
+        }
+        date = date.succ();
+    }
+    // launches on a date and rules repeating every few weeks can be further away than the lookahead
+    let launches = plan_settings.once.iter()
+        .filter(|once| !once.done && after < once.at)
+        .filter(|once| is_active(&plan_settings, &settings.holidays, once.at.date()) && !settings.is_quiet(once.at))
+        .map(|once| once.at);
+    let repeats = plan_settings.rules.iter().filter_map(|rule| {
+        let mut on = rule.repeat.next_from(date);
+        for _ in 0..MAX_SKIPPED {
+            let at = on.and_time(rule.time);
+            if is_active(&plan_settings, &settings.holidays, on) && !settings.is_quiet(at) {
+                return Some(at)
+            }
+            on = rule.repeat.next_from(on.succ());
+        }
+        None
+    });
+    launches.chain(repeats).min()
+}
+
+/// The first `n` launches of all the plans after `after`, in order, with the index of their plan,
+/// leaving out those the loop is paused for.
+pub fn upcoming(plans: &[Plan], settings: &Settings, after: NaiveDateTime, n: usize) -> Vec<(usize, NaiveDateTime)> {
+    // a launch right when the pause ends still happens
+    let after = match settings.paused_until {
+        Some(until) => after.max(until - chrono::Duration::seconds(1)),
+        None => after,
+    };
+    let mut launches = Vec::new();
+    for (index, plan) in plans.iter().enumerate() {
+        let mut at = after;
+        for _ in 0..n {
+            match next_after(plan, settings, at) {
+                Some(next) => {
+                    launches.push((index, next));
+                    at = next;
+                },
+                None => break,
+            }
+        }
+    }
+    launches.sort_by_key(|(_, at)| *at);
+    launches.truncate(n);
+    launches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autolink_lib::TimeDay;
+    use chrono::Weekday;
+    use crate::settings::{Once, Quiet, Repeat, Rule};
+
+    fn math() -> Plan {
+        Plan {
+            name: String::from("math"),
+            link: String::from("https://example.com/math"),
+            times: vec![
+                TimeDay::new(NaiveTime::from_hms(10, 30, 0), Weekday::Wed),
+                TimeDay::new(NaiveTime::from_hms(13, 15, 0), Weekday::Fri),
+            ],
+        }
+    }
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2024, 1, day).and_hms(hour, minute, 0)
+    }
+
+    #[test]
+    fn weekly_times_come_in_order() {
+        let settings = Settings::default();
+        assert_eq!(next_after(&math(), &settings, at(1, 9, 0)), Some(at(3, 10, 30)));
+        assert_eq!(next_after(&math(), &settings, at(3, 10, 30)), Some(at(5, 13, 15)));
+    }
+
+    #[test]
+    fn holidays_and_skipped_days_launch_nothing() {
+        let mut settings = Settings::default();
+        settings.holidays.push(NaiveDate::from_ymd(2024, 1, 3));
+        assert_eq!(next_after(&math(), &settings, at(1, 9, 0)), Some(at(5, 13, 15)));
+        settings.plans.entry(String::from("math")).or_default().skipped.push(NaiveDate::from_ymd(2024, 1, 5));
+        assert_eq!(next_after(&math(), &settings, at(1, 9, 0)), Some(at(10, 10, 30)));
+    }
+
+    #[test]
+    fn a_rule_every_52_weeks_is_found_a_year_ahead() {
+        let plan = Plan { times: Vec::new(), ..math() };
+        let mut settings = Settings::default();
+        settings.plans.entry(String::from("math")).or_default().rules.push(Rule {
+            repeat: Repeat::EveryNWeeks { weeks: 52, day: Weekday::Mon, anchor: NaiveDate::from_ymd(2024, 1, 1) },
+            time: NaiveTime::from_hms(8, 0, 0),
+        });
+        assert_eq!(next_after(&plan, &settings, at(1, 7, 0)), Some(at(1, 8, 0)));
+        assert_eq!(next_after(&plan, &settings, at(1, 9, 0)), Some(NaiveDate::from_ymd(2024, 12, 30).and_hms(8, 0, 0)));
+    }
+
+    #[test]
+    fn launches_on_a_date_far_ahead_are_found_until_done() {
+        let plan = Plan { times: Vec::new(), ..math() };
+        let far = NaiveDate::from_ymd(2024, 6, 1).and_hms(12, 0, 0);
+        let mut settings = Settings::default();
+        settings.plans.entry(String::from("math")).or_default().once.push(Once::new(far));
+        assert_eq!(next_after(&plan, &settings, at(1, 9, 0)), Some(far));
+        settings.plans.get_mut("math").unwrap().once[0].done = true;
+        assert_eq!(next_after(&plan, &settings, at(1, 9, 0)), None);
+    }
+
+    #[test]
+    fn between_leaves_out_quiet_times() {
+        let mut settings = Settings::default();
+        assert_eq!(between(&math(), &settings, at(1, 0, 0), at(7, 23, 59)), vec![at(3, 10, 30), at(5, 13, 15)]);
+        settings.quiet.push(Quiet { days: Vec::new(), from: NaiveTime::from_hms(13, 0, 0), until: NaiveTime::from_hms(14, 0, 0) });
+        assert_eq!(between(&math(), &settings, at(1, 0, 0), at(7, 23, 59)), vec![at(3, 10, 30)]);
+    }
+
+    #[test]
+    fn upcoming_starts_where_the_pause_ends() {
+        let mut settings = Settings { paused_until: Some(at(3, 10, 30)), ..Settings::default() };
+        assert_eq!(upcoming(&[math()], &settings, at(1, 9, 0), 2), vec![(0, at(3, 10, 30)), (0, at(5, 13, 15))]);
+        settings.paused_until = Some(at(4, 0, 0));
+        assert_eq!(upcoming(&[math()], &settings, at(1, 9, 0), 1), vec![(0, at(5, 13, 15))]);
+    }
+}
@@ -0,0 +1,372 @@
+use autolink_lib::chrono;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::{profiles, store};
+
+/*
+ * autolink_lib::Plan only knows a name, a link and some times,
+ * everything else the app knows about a plan lives here
+ * and is saved next to ~/.autolink
+*/
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Keyed by the name of the plan.
+    pub plans: HashMap<String, PlanSettings>,
+    /// Days nothing is launched on, whatever the plans say.
+    pub holidays: Vec<NaiveDate>,
+    /// Times of the day nothing is launched in, whatever the plans say.
+    pub quiet: Vec<Quiet>,
+    /// Nothing is launched before this, only set while the loop is paused.
+    pub paused_until: Option<NaiveDateTime>,
+    /// Up to when the loop had checked the plans when the app was closed,
+    /// only set if the loop was running then.
+    pub checked_until: Option<NaiveDateTime>,
+}
+
+impl Settings {
+    pub fn path(profile: &str) -> PathBuf {
+        profiles::settings_path(profile)
+    }
+
+    /// Reads the settings, a missing or unreadable file gives the defaults.
+    pub fn load(path: PathBuf) -> Self {
+        Self::read(path).unwrap_or_default()
+    }
+
+    /// Reads the settings, giving nothing for a missing or unreadable file.
+    pub fn read(path: PathBuf) -> Option<Self> {
+        std::fs::read_to_string(path).ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+    }
+
+    pub fn save(&self, path: PathBuf) -> Result<(), String> {
+        let contents = serde_json::to_string_pretty(self).unwrap();
+        store::atomically(&path, |tmp| std::fs::write(tmp, contents))
+            .map_err(|e| format!("couldn't save {}: {}", path.display(), e))
+    }
+
+    /// Whether `at` falls in any of the quiet times.
+    pub fn is_quiet(&self, at: NaiveDateTime) -> bool {
+        self.quiet.iter().any(|quiet| quiet.contains(at))
+    }
+
+    pub fn plan(&self, name: &str) -> PlanSettings {
+        self.plans.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Moves the settings of a plan along when it is renamed.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if from != to {
+            if let Some(settings) = self.plans.remove(from) {
+                self.plans.insert(to.to_string(), settings);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlanSettings {
+    /// A disabled plan launches nothing until it is enabled again.
+    pub enabled: bool,
+    /// The plan launches nothing before this day.
+    pub paused_until: Option<NaiveDate>,
+    /// Done in order after the link of the plan is opened, plans used to only have links here.
+    #[serde(alias = "links")]
+    pub actions: Vec<Action>,
+    /// What the links of the plan are opened with.
+    pub launcher: Launcher,
+    pub catch_up: CatchUp,
+    /// Launches on a date, on top of the weekly times of the plan.
+    pub once: Vec<Once>,
+    /// Launches that repeat other than weekly, on top of the weekly times of the plan.
+    pub rules: Vec<Rule>,
+    /// The first day the plan launches on, if it doesn't launch from the start.
+    pub active_from: Option<NaiveDate>,
+    /// The last day the plan launches on, if it doesn't launch forever.
+    pub active_until: Option<NaiveDate>,
+    /// Days the plan doesn't launch on.
+    pub skipped: Vec<NaiveDate>,
+}
+
+impl Default for PlanSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            paused_until: None,
+            actions: Vec::new(),
+            launcher: Launcher::default(),
+            catch_up: CatchUp::default(),
+            once: Vec::new(),
+            rules: Vec::new(),
+            active_from: None,
+            active_until: None,
+            skipped: Vec::new(),
+        }
+    }
+}
+
+/// Something a plan does after opening its link, `delay_secs` after whatever it did before.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Action {
+    pub delay_secs: u64,
+    #[serde(flatten)]
+    pub kind: ActionKind,
+}
+
+impl Action {
+    /// The link, or the command line of the program.
+    pub fn label(&self) -> String {
+        match &self.kind {
+            ActionKind::Link { url } => url.clone(),
+            ActionKind::Command(program) => std::iter::once(&program.program).chain(&program.args)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Told apart by their fields, a link has a `url` and a command a `program`.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ActionKind {
+    /// Opened with the launcher of the plan.
+    Link { url: String },
+    Command(Program),
+}
+
+/// A program run on its own, not through a shell, its output goes to the history.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Program {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Where it runs, where the app runs if not given.
+    pub dir: Option<String>,
+    /// Set on top of the environment of the app.
+    pub env: Vec<(String, String)>,
+    /// It is killed once it has run for this long.
+    pub timeout_secs: u64,
+}
+
+impl Default for Program {
+    fn default() -> Self {
+        Self {
+            program: String::new(),
+            args: Vec::new(),
+            dir: None,
+            env: Vec::new(),
+            timeout_secs: 60,
+        }
+    }
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum Launcher {
+    /// The default browser of the system.
+    #[default]
+    System,
+    /// A browser, given the arguments and then the link, like `firefox -P work`.
+    Browser { program: String, args: Vec<String> },
+    /// A command line split into words like a shell does, quotes keeping words together,
+    /// where `{link}` is replaced by the link in each word.
+    Command { template: String },
+}
+
+/// A launch that only happens once, it is marked done once its time has passed.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Once {
+    pub at: NaiveDateTime,
+    pub done: bool,
+}
+
+impl Once {
+    pub fn new(at: NaiveDateTime) -> Self {
+        Self { at, done: false }
+    }
+
+    pub fn label(&self) -> String {
+        match self.done {
+            true => format!("{} (once, done)", self.at.format("%Y-%m-%d %H:%M")),
+            false => format!("{} (once)", self.at.format("%Y-%m-%d %H:%M")),
+        }
+    }
+}
+
+/// What to do with a launch that was missed,
+/// because the machine was asleep, the app was closed or the clock jumped.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CatchUp {
+    /// Launch it late, as long as it is at most this many minutes late.
+    Launch { grace_minutes: i64 },
+    /// Ask the user whether to launch it.
+    Ask,
+    /// Skip it, only noting that it was missed.
+    Skip,
+}
+
+impl Default for CatchUp {
+    fn default() -> Self {
+        CatchUp::Launch { grace_minutes: 15 }
+    }
+}
+
+/// A time of the day nothing is launched in, on the given days or on every day if none are given.
+/// It goes on past midnight when it ends before it starts, the days are those it starts on.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quiet {
+    pub days: Vec<Weekday>,
+    pub from: NaiveTime,
+    pub until: NaiveTime,
+}
+
+impl Quiet {
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let starts_on = |day: Weekday| self.days.is_empty() || self.days.contains(&day);
+        let time = at.time();
+        if self.from <= self.until {
+            starts_on(at.weekday()) && self.from <= time && time < self.until
+        } else {
+            (starts_on(at.weekday()) && self.from <= time) || (starts_on(at.weekday().pred()) && time < self.until)
+        }
+    }
+}
+
+/// A launch that repeats at a time, on the days the repeat falls on.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub repeat: Repeat,
+    pub time: NaiveTime,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Repeat {
+    Daily,
+    /// Monday to Friday.
+    Weekdays,
+    /// On `day` every `weeks` weeks, counting from the week `anchor` is in.
+    EveryNWeeks { weeks: u32, day: Weekday, anchor: NaiveDate },
+    /// On the `nth` `day` of every month, where 5 is the last one.
+    MonthlyNth { nth: u32, day: Weekday },
+}
+
+impl Rule {
+    pub fn label(&self) -> String {
+        let time = self.time.format("%H:%M");
+        match self.repeat {
+            Repeat::Daily => format!("daily - {}", time),
+            Repeat::Weekdays => format!("weekdays - {}", time),
+            Repeat::EveryNWeeks { weeks, day, anchor } => {
+                format!("every {} weeks on {} from {} - {}", weeks, day, anchor.format("%Y-%m-%d"), time)
+            },
+            Repeat::MonthlyNth { nth, day } => {
+                let nths = ["first", "second", "third", "fourth", "last"];
+                // a file changed by hand may have any number here
+                let nth = match (nth as usize).checked_sub(1).and_then(|i| nths.get(i)) {
+                    Some(nth) => nth.to_string(),
+                    None => format!("{}th", nth),
+                };
+                format!("{} {} of the month - {}", nth, day, time)
+            },
+        }
+    }
+}
+
+impl Repeat {
+    pub fn falls_on(&self, date: NaiveDate) -> bool {
+        match *self {
+            Repeat::Daily => true,
+            Repeat::Weekdays => date.weekday().num_days_from_monday() < 5,
+            Repeat::EveryNWeeks { weeks, day, anchor } => {
+                let monday = |date: NaiveDate| date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+                let since = (monday(date) - monday(anchor)).num_days() / 7;
+                date.weekday() == day && since.rem_euclid(weeks.max(1) as i64) == 0
+            },
+            Repeat::MonthlyNth { nth, day } => {
+                let is_last = (date + chrono::Duration::days(7)).month() != date.month();
+                date.weekday() == day && ((date.day() - 1) / 7 + 1 == nth || (nth == 5 && is_last))
+            },
+        }
+    }
+
+    /// The first date on or after `date` this falls on, worked out rather than looked for,
+    /// since every few weeks can be most of a year away.
+    pub fn next_from(&self, date: NaiveDate) -> NaiveDate {
+        match *self {
+            Repeat::EveryNWeeks { weeks, day, anchor } => {
+                let ahead = (day.num_days_from_monday() + 7 - date.weekday().num_days_from_monday()) % 7;
+                let date = date + chrono::Duration::days(ahead as i64);
+                let monday = |date: NaiveDate| date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+                let since = (monday(date) - monday(anchor)).num_days() / 7;
+                let weeks = weeks.max(1) as i64;
+                date + chrono::Duration::weeks((weeks - since.rem_euclid(weeks)) % weeks)
+            },
+            // the others come around within a month
+            _ => {
+                let mut date = date;
+                while !self.falls_on(date) {
+                    date = date.succ();
+                }
+                date
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2024, 1, day).and_hms(hour, minute, 0)
+    }
+
+    fn quiet(days: Vec<Weekday>, from: (u32, u32), until: (u32, u32)) -> Quiet {
+        Quiet { days, from: NaiveTime::from_hms(from.0, from.1, 0), until: NaiveTime::from_hms(until.0, until.1, 0) }
+    }
+
+    #[test]
+    fn a_quiet_time_within_a_day_starts_in_and_ends_out() {
+        let lunch = quiet(Vec::new(), (12, 0), (13, 0));
+        assert!(!lunch.contains(at(3, 11, 59)));
+        assert!(lunch.contains(at(3, 12, 0)));
+        assert!(lunch.contains(at(6, 12, 59)));
+        assert!(!lunch.contains(at(3, 13, 0)));
+    }
+
+    #[test]
+    fn a_quiet_time_goes_on_past_midnight_from_the_days_it_starts_on() {
+        // friday the 5th
+        let night = quiet(vec![Weekday::Fri], (22, 0), (7, 0));
+        assert!(night.contains(at(5, 22, 0)));
+        assert!(night.contains(at(5, 23, 59)));
+        assert!(night.contains(at(6, 6, 59)));
+        assert!(!night.contains(at(6, 7, 0)));
+        assert!(!night.contains(at(5, 6, 0)));
+        assert!(!night.contains(at(4, 23, 0)));
+        assert!(!night.contains(at(6, 22, 0)));
+    }
+
+    #[test]
+    fn any_quiet_time_makes_it_quiet() {
+        let mut settings = Settings::default();
+        assert!(!settings.is_quiet(at(3, 12, 30)));
+        settings.quiet.push(quiet(Vec::new(), (22, 0), (7, 0)));
+        settings.quiet.push(quiet(vec![Weekday::Wed], (12, 0), (13, 0)));
+        assert!(settings.is_quiet(at(3, 12, 30)));
+        assert!(!settings.is_quiet(at(4, 12, 30)));
+        assert!(settings.is_quiet(at(1, 0, 30)));
+    }
+
+    #[test]
+    fn monthly_repeats_have_a_label_whatever_their_number() {
+        let rule = |nth| Rule { repeat: Repeat::MonthlyNth { nth, day: Weekday::Thu }, time: NaiveTime::from_hms(9, 0, 0) };
+        assert_eq!(rule(5).label(), "last Thu of the month - 09:00");
+        assert_eq!(rule(0).label(), "0th Thu of the month - 09:00");
+        assert_eq!(rule(7).label(), "7th Thu of the month - 09:00");
+    }
+}
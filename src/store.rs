@@ -0,0 +1,421 @@
+use autolink_lib::Plan;
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender};
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use crate::{instance, profiles};
+use crate::scheduler::SchedulerCommand;
+use crate::settings::Settings;
+
+mod format;
+mod merge;
+
+/// How many of the previous files are kept, as ~/.autolink.1 and so on.
+const BACKUPS: usize = 3;
+
+/// How long the writer waits for more changes before it writes.
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// How often the writer looks at the file when it has nothing to write.
+const POLL: Duration = Duration::from_secs(3);
+
+/*
+ * the plans of a profile live in ~/.autolink or next to it, in the format described
+ * in format.rs, the app, the daemon and the command line all go through here
+ * 
+ * a write goes to a file next to it first, which is then renamed over it,
+ * so a crash or a power loss leaves either the old plans or the new ones
+*/
+pub fn path(profile: &str) -> PathBuf {
+    profiles::plans_path(profile)
+}
+
+/// Reads the plans from a file of any version so far, leaving the file as it is.
+pub fn load(profile: &str) -> Result<Vec<Plan>, String> {
+    load_from(&path(profile))
+}
+
+pub fn load_from(path: &Path) -> Result<Vec<Plan>, String> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("couldn't read {}: {}", path.display(), e)),
+    };
+    if !format::is_legacy(&contents) {
+        return format::parse(&contents).map_err(|e| format!("couldn't read {}: {}", path.display(), e))
+    }
+    // autolink_lib gives nothing back for a file it can't read
+    let plans = autolink_lib::import(path.to_path_buf());
+    if plans.is_empty() && contents.trim() != "" && contents.trim() != "[]" {
+        return Err(format!("couldn't read {}, it is in no format autolink knows", path.display()))
+    }
+    Ok(plans)
+}
+
+/// Moves the file of a profile to the current format if it is older,
+/// only the instance holding the lock does, so that nothing else writes it meanwhile.
+pub fn migrate(profile: &str, _lock: &instance::Lock) -> Result<(), String> {
+    let contents = std::fs::read_to_string(path(profile)).unwrap_or_default();
+    if contents.trim() == "" || !format::is_legacy(&contents) {
+        return Ok(())
+    }
+    save(profile, load(profile)?)
+}
+
+pub fn save(profile: &str, plans: Vec<Plan>) -> Result<(), String> {
+    save_to(&path(profile), plans)
+}
+
+pub fn save_to(path: &Path, plans: Vec<Plan>) -> Result<(), String> {
+    back_up(path);
+    atomically(path, |tmp| std::fs::write(tmp, format::write(&plans)))
+        .map_err(|e| format!("couldn't save {}: {}", path.display(), e))
+}
+
+/// Moves a file that couldn't be read out of the way, so that the app can start over.
+pub fn set_aside(profile: &str) -> io::Result<PathBuf> {
+    let aside = beside(&path(profile), "corrupt");
+    std::fs::rename(path(profile), &aside)?;
+    Ok(aside)
+}
+
+/// Writes to a temporary file with `write`, then puts it in place of `path`,
+/// `path` is left as it was if anything goes wrong before that.
+pub fn atomically(path: &Path, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+    let tmp = beside(path, "tmp");
+    if let Err(e) = write(&tmp).and_then(|_| File::open(&tmp)?.sync_all()) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(e)
+    }
+    std::fs::rename(&tmp, path)?;
+    sync_dir(path)
+}
+
+/// Makes the rename of a file stick, which is only on disk once its folder is.
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) => File::open(dir)?.sync_all(),
+        None => Ok(()),
+    }
+}
+
+/// Folders can't be opened like files elsewhere, the rename is left to the system.
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// A file next to `path` named after all of it, the plans of a profile and its
+/// settings only differ by an extension, so theirs can't be swapped out.
+fn beside(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+/// Shifts the backups along by one and copies the file to the first one.
+fn back_up(path: &Path) {
+    if !path.exists() {
+        return
+    }
+    let backup = |n: usize| beside(path, &n.to_string());
+    for n in (1..BACKUPS).rev() {
+        let _ = std::fs::rename(backup(n), backup(n + 1));
+    }
+    let _ = std::fs::copy(path, backup(1));
+}
+
+/*
+ * the app saves after every change, which can be many changes a second while typing,
+ * so they go to a thread which writes once nothing has changed for a moment
+ * 
+ * while there is nothing to write, the same thread watches the file for changes
+ * made by something else, like a folder synced between computers, merges them into
+ * the plans and hands those to the loop, if it runs them
+ * 
+ * it writes and watches the settings file too, but the settings are kept by the app,
+ * so it only tells the app how the file changed, for the app to merge it
+*/
+pub struct Writer {
+    sender: Sender<Write>,
+    thread: std::thread::JoinHandle<()>,
+}
+
+/// What the writer is given to write.
+enum Write {
+    Plans(Vec<Plan>),
+    Settings(Settings),
+}
+
+/// What the writer did about a change made to ~/.autolink by something else,
+/// or that it couldn't write the plans.
+pub enum FileEvent {
+    Reloaded(usize),
+    /// The entries that were changed both on disk and in the app, the app's changes were kept.
+    Conflicted(Vec<String>),
+    /// Why the plans or settings couldn't be saved, they are written again with the next change.
+    Failed(String),
+    /// The settings file changed, `known` is how it was before, for `merge_settings`.
+    /// Boxed, as settings are much bigger than the other events.
+    SettingsChanged { known: Box<Settings>, disk: Box<Settings> },
+}
+
+/// What the writer needs to keep the file and the plans in memory in step.
+struct Watch {
+    profile: String,
+    plans: Arc<Mutex<Vec<Plan>>>,
+    /// Only there for the profile the loop runs.
+    commands: Option<Sender<SchedulerCommand>>,
+    events: Sender<FileEvent>,
+    /// The plans as they were last read from or written to the file.
+    known: Vec<Plan>,
+    modified: Option<SystemTime>,
+    /// A change is only read once the file has stopped changing for a poll.
+    seen: Option<SystemTime>,
+    /// The same for the settings file.
+    known_settings: Settings,
+    settings_modified: Option<SystemTime>,
+    settings_seen: Option<SystemTime>,
+}
+
+impl Writer {
+    pub fn spawn(
+        profile: &str,
+        plans: Arc<Mutex<Vec<Plan>>>,
+        commands: Option<Sender<SchedulerCommand>>,
+    ) -> (Self, Receiver<FileEvent>) {
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let (event_sender, events) = crossbeam_channel::unbounded();
+        let known = plans.lock().unwrap().clone();
+        let mut watch = Watch {
+            profile: profile.to_string(),
+            plans,
+            commands,
+            events: event_sender,
+            known,
+            modified: modified(&path(profile)),
+            seen: None,
+            known_settings: Settings::load(Settings::path(profile)),
+            settings_modified: modified(&Settings::path(profile)),
+            settings_seen: None,
+        };
+        watch.seen = watch.modified;
+        watch.settings_seen = watch.settings_modified;
+        let thread = std::thread::spawn(move || watch.run(receiver));
+        (Self { sender, thread }, events)
+    }
+
+    pub fn write(&self, plans: Vec<Plan>) {
+        let _ = self.sender.send(Write::Plans(plans));
+    }
+
+    pub fn write_settings(&self, settings: Settings) {
+        let _ = self.sender.send(Write::Settings(settings));
+    }
+
+    /// Writes whatever is still waiting, and returns once it is on disk.
+    pub fn finish(self) {
+        drop(self.sender);
+        let _ = self.thread.join();
+    }
+}
+
+impl Watch {
+    fn run(&mut self, receiver: Receiver<Write>) {
+        let mut waiting: Option<Vec<Plan>> = None;
+        let mut waiting_settings: Option<Settings> = None;
+        loop {
+            let timeout = match waiting.is_some() || waiting_settings.is_some() {
+                true => DEBOUNCE,
+                false => POLL,
+            };
+            match receiver.recv_timeout(timeout) {
+                Ok(Write::Plans(plans)) => waiting = Some(plans),
+                Ok(Write::Settings(settings)) => waiting_settings = Some(settings),
+                Err(RecvTimeoutError::Timeout) => match (waiting.take(), waiting_settings.take()) {
+                    (None, None) => self.check(),
+                    (plans, settings) => self.write_all(plans, settings),
+                },
+                Err(RecvTimeoutError::Disconnected) => {
+                    self.write_all(waiting, waiting_settings);
+                    return
+                },
+            }
+        }
+    }
+
+    fn write_all(&mut self, plans: Option<Vec<Plan>>, settings: Option<Settings>) {
+        if let Some(plans) = plans {
+            self.write(plans);
+        }
+        if let Some(settings) = settings {
+            self.write_settings(settings);
+        }
+    }
+
+    fn write(&mut self, plans: Vec<Plan>) {
+        if merge::same_plans(&plans, &self.known) {
+            return
+        }
+        if let Err(e) = save(&self.profile, plans.clone()) {
+            let _ = self.events.send(FileEvent::Failed(e));
+            return
+        }
+        self.known = plans;
+        self.modified = modified(&path(&self.profile));
+        self.seen = self.modified;
+    }
+
+    fn write_settings(&mut self, settings: Settings) {
+        if merge::equal(&settings, &self.known_settings) {
+            return
+        }
+        if let Err(e) = settings.save(Settings::path(&self.profile)) {
+            let _ = self.events.send(FileEvent::Failed(e));
+            return
+        }
+        self.known_settings = settings;
+        self.settings_modified = modified(&Settings::path(&self.profile));
+        self.settings_seen = self.settings_modified;
+    }
+
+    fn check(&mut self) {
+        self.check_settings();
+        self.check_plans();
+    }
+
+    fn check_settings(&mut self) {
+        let modified = modified(&Settings::path(&self.profile));
+        let seen = std::mem::replace(&mut self.settings_seen, modified);
+        if modified == self.settings_modified || modified != seen {
+            return
+        }
+        self.settings_modified = modified;
+        let disk = match Settings::read(Settings::path(&self.profile)) {
+            Some(disk) => disk,
+            None => return,
+        };
+        if merge::equal(&disk, &self.known_settings) {
+            return
+        }
+        let known = std::mem::replace(&mut self.known_settings, disk.clone());
+        let _ = self.events.send(FileEvent::SettingsChanged { known: Box::new(known), disk: Box::new(disk) });
+    }
+
+    fn check_plans(&mut self) {
+        let modified = modified(&path(&self.profile));
+        let seen = std::mem::replace(&mut self.seen, modified);
+        if modified == self.modified || modified != seen {
+            return
+        }
+        self.modified = modified;
+        // a file being synced may be half written, it is read again once it changes
+        let disk = match load(&self.profile) {
+            Ok(disk) => disk,
+            Err(_) => return,
+        };
+        if merge::same_plans(&disk, &self.known) {
+            return
+        }
+        let mut plans = self.plans.lock().unwrap();
+        let (merged, conflicts) = merge::merge(&self.known, &plans, &disk);
+        *plans = merged.clone();
+        drop(plans);
+        if let Some(commands) = &self.commands {
+            let _ = commands.send(SchedulerCommand::ReloadPlans(merged.clone()));
+        }
+        let _ = self.events.send(match conflicts.is_empty() {
+            true => FileEvent::Reloaded(merged.len()),
+            false => FileEvent::Conflicted(conflicts),
+        });
+        self.known = disk;
+        // the app's own changes still have to get to the file
+        self.write(merged);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Whether two plans are the same in every way.
+pub fn same_plan(a: &Plan, b: &Plan) -> bool {
+    merge::same(Some(a), Some(b))
+}
+
+/// Merges the settings file changed on disk into those of the app, gives the names of the plans
+/// whose settings were changed on both sides, the app's were kept for those.
+pub fn merge_settings(known: &Settings, local: &Settings, disk: &Settings) -> (Settings, Vec<String>) {
+    merge::merge_settings(known, local, disk)
+}
+
+/// Whether two settings are the same in every way.
+pub fn same_settings(a: &Settings, b: &Settings) -> bool {
+    merge::equal(a, b)
+}
+
+/// Makes sure no entry but the one at `except` is called `name`, as settings are kept by name.
+pub fn check_name(plans: &[Plan], name: &str, except: Option<usize>) -> Result<(), String> {
+    match plans.iter().position(|plan| plan.name == name) {
+        Some(i) if Some(i) != except => Err(format!("there is an entry called {} already", name)),
+        _ => Ok(()),
+    }
+}
+
+/// Finds an entry by its index, or else by its name.
+pub fn find(plans: &[Plan], entry: &str) -> Result<usize, String> {
+    if let Ok(i) = entry.parse::<usize>() {
+        if i < plans.len() {
+            return Ok(i)
+        }
+    }
+    plans.iter().position(|plan| plan.name == entry)
+        .ok_or(format!("there is no entry called {}", entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autolink_lib::chrono::{NaiveTime, Weekday};
+    use autolink_lib::TimeDay;
+    use crate::settings::Settings;
+
+    #[test]
+    fn plans_and_settings_of_a_profile_are_saved_apart() {
+        // a folder of its own in place of the home folder, which other tests may be using
+        let home = std::env::temp_dir().join(format!("autolink-store-{}", std::process::id()));
+        std::fs::create_dir_all(&home).unwrap();
+        profiles::create_in(&home, "term").unwrap();
+        let (plans_path, settings_path) = (profiles::plans_path_in(&home, "term"), profiles::settings_path_in(&home, "term"));
+        let plans = vec![Plan {
+            name: String::from("math"),
+            link: String::from("https://example.com/math"),
+            times: vec![TimeDay::new(NaiveTime::from_hms(9, 30, 0), Weekday::Mon)],
+        }];
+        let mut settings = Settings::default();
+        settings.plans.insert(String::from("math"), Default::default());
+        let writing = std::thread::spawn({
+            let (plans, plans_path) = (plans.clone(), plans_path.clone());
+            move || for _ in 0..50 {
+                save_to(&plans_path, plans.clone()).unwrap();
+            }
+        });
+        for _ in 0..50 {
+            settings.save(settings_path.clone()).unwrap();
+        }
+        writing.join().unwrap();
+
+        let loaded = load_from(&plans_path).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].name, "math");
+        let contents = std::fs::read_to_string(&settings_path).unwrap();
+        let loaded: Settings = serde_json::from_str(&contents).unwrap();
+        assert!(loaded.plans.contains_key("math"));
+        assert_eq!(profiles::list_in(&home), vec![String::from(profiles::DEFAULT), String::from("term")]);
+        let _ = std::fs::remove_dir_all(home);
+    }
+}
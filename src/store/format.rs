@@ -0,0 +1,134 @@
+use autolink_lib::{Plan, TimeDay};
+use autolink_lib::chrono::{NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the file changes in a way older versions can't read.
+pub const VERSION: u32 = 1;
+
+/*
+ * ~/.autolink is json, made to be read and changed by hand:
+ * 
+ * {
+ *   "version": 1,
+ *   "plans": [
+ *     { "name": "maths", "link": "https://example.com/maths", "times": ["Mon 09:00", "Wed 10:30"] }
+ *   ]
+ * }
+ * 
+ * files without a version are what autolink_lib used to write, they are
+ * read with autolink_lib itself, so whatever it wrote is read as it meant it
+*/
+#[derive(Serialize, Deserialize)]
+struct File {
+    version: u32,
+    plans: Vec<Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    name: String,
+    link: String,
+    #[serde(default)]
+    times: Vec<String>,
+}
+
+/// Reads the plans from a file with a version, see `is_legacy` for those without.
+pub fn parse(contents: &str) -> Result<Vec<Plan>, String> {
+    let value: serde_json::Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    match value.get("version").and_then(|version| version.as_u64()) {
+        None => Err(String::from("it has no version")),
+        Some(version) if version > VERSION as u64 => Err(format!(
+            "it was written by a newer autolink, as version {} while this one knows up to {}", version, VERSION
+        )),
+        Some(_) => {
+            let file: File = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            file.plans.into_iter().map(from_entry).collect()
+        },
+    }
+}
+
+/// Whether the file is still in the format autolink_lib used to write, which is anything without a version.
+pub fn is_legacy(contents: &str) -> bool {
+    let value = serde_json::from_str::<serde_json::Value>(contents);
+    !value.map(|value| value.get("version").is_some()).unwrap_or(false)
+}
+
+pub fn write(plans: &[Plan]) -> String {
+    let file = File {
+        version: VERSION,
+        plans: plans.iter().map(to_entry).collect(),
+    };
+    serde_json::to_string_pretty(&file).unwrap()
+}
+
+fn to_entry(plan: &Plan) -> Entry {
+    Entry {
+        name: plan.name.clone(),
+        link: plan.link.clone(),
+        times: plan.times.iter().map(|TimeDay { day, time }| format!("{} {}", day, time.format("%H:%M"))).collect(),
+    }
+}
+
+fn from_entry(entry: Entry) -> Result<Plan, String> {
+    let mut times = Vec::new();
+    for time in &entry.times {
+        let mut parts = time.split_whitespace();
+        let timeday = match (parts.next(), parts.next(), parts.next()) {
+            (Some(day), Some(at), None) => day.parse::<Weekday>().ok()
+                .and_then(|day| NaiveTime::parse_from_str(at, "%H:%M").ok().map(|at| TimeDay::new(at, day))),
+            _ => None,
+        };
+        times.push(timeday.ok_or(format!("{} has the time \"{}\", which isn't like \"Mon 09:30\"", entry.name, time))?);
+    }
+    Ok(Plan { name: entry.name, link: entry.link, times })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn math() -> Plan {
+        Plan {
+            name: String::from("math"),
+            link: String::from("https://example.com/math"),
+            times: vec![
+                TimeDay::new(NaiveTime::from_hms(9, 0, 0), Weekday::Mon),
+                TimeDay::new(NaiveTime::from_hms(10, 30, 0), Weekday::Wed),
+            ],
+        }
+    }
+
+    #[test]
+    fn files_without_a_version_are_legacy() {
+        assert!(is_legacy(r#"[{"name": "math", "link": "https://example.com/math", "times": []}]"#));
+        assert!(is_legacy("not json at all"));
+        assert!(!is_legacy(&write(&[math()])));
+    }
+
+    #[test]
+    fn written_plans_read_back_the_same() {
+        let written = write(&[math()]);
+        assert!(written.contains(r#""Wed 10:30""#));
+        let read = parse(&written).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].name, "math");
+        assert_eq!(read[0].link, "https://example.com/math");
+        assert_eq!(read[0].times.len(), 2);
+        assert!(read[0].times.iter().all(|time| autolink_lib::check(&math(), time)));
+    }
+
+    #[test]
+    fn entries_may_leave_out_their_times() {
+        let read = parse(r#"{"version": 1, "plans": [{"name": "math", "link": "https://example.com/math"}]}"#).unwrap();
+        assert!(read[0].times.is_empty());
+    }
+
+    #[test]
+    fn newer_versions_and_odd_times_are_refused() {
+        let newer = parse(r#"{"version": 2, "plans": []}"#).unwrap_err();
+        assert!(newer.contains("newer autolink"));
+        let odd = parse(r#"{"version": 1, "plans": [{"name": "math", "link": "", "times": ["Monday 9"]}]}"#).unwrap_err();
+        assert_eq!(odd, "math has the time \"Monday 9\", which isn't like \"Mon 09:30\"");
+        assert_eq!(parse("[]").unwrap_err(), "it has no version");
+    }
+}
@@ -0,0 +1,169 @@
+use autolink_lib::Plan;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use crate::settings::Settings;
+
+/*
+ * when ~/.autolink changes under a running app, both sides may have changed it
+ * 
+ * entries are matched by name against the plans last known to be on disk,
+ * and entries sharing a name by which of them they are:
+ * an entry changed on one side only takes that change, an entry changed on
+ * both sides keeps the one in the app and is reported as a conflict
+*/
+pub fn merge(known: &[Plan], local: &[Plan], disk: &[Plan]) -> (Vec<Plan>, Vec<String>) {
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    for (plan, key) in disk.iter().zip(keys(disk)) {
+        let known = find(known, key);
+        let local = find(local, key);
+        if same(local, known) {
+            merged.push(plan.clone());
+        } else if same(Some(plan), known) {
+            merged.extend(local.cloned());
+        } else {
+            merged.extend(local.cloned());
+            conflicts.push(plan.name.clone());
+        }
+    }
+    for (plan, key) in local.iter().zip(keys(local)).filter(|(_, key)| find(disk, *key).is_none()) {
+        let known = find(known, key);
+        if known.is_none() {
+            // added in the app
+            merged.push(plan.clone());
+        } else if !same(Some(plan), known) {
+            // removed on disk, but changed in the app
+            merged.push(plan.clone());
+            conflicts.push(plan.name.clone());
+        }
+    }
+    (merged, conflicts)
+}
+
+pub fn same_plans(a: &[Plan], b: &[Plan]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(Some(a), Some(b)))
+}
+
+/*
+ * the settings file is merged the same way, the settings of each plan on their own
+ * and the holidays and quiet times as one, where the loop is and its pause are left as the app has them
+*/
+pub fn merge_settings(known: &Settings, local: &Settings, disk: &Settings) -> (Settings, Vec<String>) {
+    let mut merged = local.clone();
+    let mut conflicts = Vec::new();
+    let names: BTreeSet<&String> = known.plans.keys().chain(local.plans.keys()).chain(disk.plans.keys()).collect();
+    for name in names {
+        let (known, local, disk) = (known.plans.get(name), local.plans.get(name), disk.plans.get(name));
+        if equal(&local, &known) {
+            match disk {
+                Some(settings) => merged.plans.insert(name.clone(), settings.clone()),
+                None => merged.plans.remove(name),
+            };
+        } else if !equal(&disk, &known) && !equal(&local, &disk) {
+            conflicts.push(name.clone());
+        }
+    }
+    let days = |settings: &Settings| (settings.holidays.clone(), settings.quiet.clone());
+    if equal(&days(local), &days(known)) {
+        merged.holidays = disk.holidays.clone();
+        merged.quiet = disk.quiet.clone();
+    } else if !equal(&days(disk), &days(known)) && !equal(&days(local), &days(disk)) {
+        conflicts.push(String::from("the holidays and quiet times"));
+    }
+    (merged, conflicts)
+}
+
+/// An entry is its name and how many entries before it have that name too.
+fn keys(plans: &[Plan]) -> Vec<(&str, usize)> {
+    plans.iter().enumerate()
+        .map(|(i, plan)| (plan.name.as_str(), plans[..i].iter().filter(|other| other.name == plan.name).count()))
+        .collect()
+}
+
+fn find<'a>(plans: &'a [Plan], (name, nth): (&str, usize)) -> Option<&'a Plan> {
+    plans.iter().filter(|plan| plan.name == name).nth(nth)
+}
+
+/// Plan can't be compared, but what it is saved as can.
+pub fn same(a: Option<&Plan>, b: Option<&Plan>) -> bool {
+    equal(&a, &b)
+}
+
+/// Whether two things are saved the same.
+pub fn equal<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).unwrap() == serde_json::to_value(b).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use autolink_lib::chrono::NaiveDate;
+
+    fn plan(name: &str, link: &str) -> Plan {
+        Plan { name: String::from(name), link: String::from(link), times: Vec::new() }
+    }
+
+    fn links(plans: &[Plan]) -> Vec<&str> {
+        plans.iter().map(|plan| plan.link.as_str()).collect()
+    }
+
+    #[test]
+    fn changes_on_either_side_are_taken() {
+        let known = vec![plan("math", "a"), plan("art", "b")];
+        let local = vec![plan("math", "a2"), plan("art", "b"), plan("music", "c")];
+        let disk = vec![plan("math", "a"), plan("art", "b2"), plan("pe", "d")];
+        let (merged, conflicts) = merge(&known, &local, &disk);
+        assert_eq!(links(&merged), vec!["a2", "b2", "d", "c"]);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn removals_are_taken_unless_the_other_side_changed_the_entry() {
+        let known = vec![plan("math", "a"), plan("art", "b")];
+        let local = vec![plan("art", "b2")];
+        let disk = vec![plan("math", "a")];
+        let (merged, conflicts) = merge(&known, &local, &disk);
+        assert_eq!(links(&merged), vec!["b2"]);
+        assert_eq!(conflicts, vec!["art"]);
+    }
+
+    #[test]
+    fn changes_on_both_sides_keep_the_app_and_conflict() {
+        let known = vec![plan("math", "a")];
+        let (merged, conflicts) = merge(&known, &[plan("math", "app")], &[plan("math", "disk")]);
+        assert_eq!(links(&merged), vec!["app"]);
+        assert_eq!(conflicts, vec!["math"]);
+    }
+
+    #[test]
+    fn entries_sharing_a_name_are_told_apart() {
+        let known = vec![plan("math", "a"), plan("math", "b")];
+        let local = vec![plan("math", "a"), plan("math", "b2")];
+        let disk = vec![plan("math", "a2"), plan("math", "b")];
+        let (merged, conflicts) = merge(&known, &local, &disk);
+        assert_eq!(links(&merged), vec!["a2", "b2"]);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn settings_are_merged_per_entry() {
+        let mut known = Settings::default();
+        known.plans.entry(String::from("math")).or_default();
+        known.plans.entry(String::from("art")).or_default();
+        let mut local = known.clone();
+        local.plans.get_mut("math").unwrap().enabled = false;
+        let mut disk = known.clone();
+        disk.plans.remove("art");
+        disk.holidays.push(NaiveDate::from_ymd(2024, 1, 1));
+        let (merged, conflicts) = merge_settings(&known, &local, &disk);
+        assert!(!merged.plan("math").enabled);
+        assert!(!merged.plans.contains_key("art"));
+        assert_eq!(merged.holidays, disk.holidays);
+        assert!(conflicts.is_empty());
+
+        local.holidays.push(NaiveDate::from_ymd(2024, 2, 1));
+        let (merged, conflicts) = merge_settings(&known, &local, &disk);
+        assert_eq!(merged.holidays, local.holidays);
+        assert_eq!(conflicts, vec!["the holidays and quiet times"]);
+    }
+}